@@ -1,19 +1,32 @@
 mod utils;
-use clap::Parser;
-use utils::types::AppResult;
+use anyhow::Context;
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use std::collections::HashMap;
+use utils::cassette::HttpClient;
+use utils::curl::export_curl;
+use utils::import::har::{import_har, HarFilter};
+use utils::import::openapi::import_openapi;
+use utils::import::postman::import_postman;
+use utils::import::write_suite;
+use utils::mock::run_mock;
+use utils::types::{AppResult, RunOptions};
 use utils::{gen_struct, render_results, run_init, run_test};
 // 引数を格納する構造体を定義
 // raxtest
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// インプットするymlファイルのパス
     #[arg(short, long, required = true)]
-    input_yml_path: String,
+    input_yml_path: Option<String>,
 
     /// 出力先のjsonファイルのパス
     #[arg(short, long, required = true)]
-    output_json_path: String,
+    output_json_path: Option<String>,
 
     /// リクエストの詳細を表示するかどうか
     /// true: 表示する
@@ -24,6 +37,150 @@ struct Args {
     /// クッキーが取得できなかった場合にテストを中断するかどうか
     #[arg(short, long, default_value = "false")]
     cookie_error_continue: bool,
+
+    /// 使用する環境の名前
+    /// テスト構成ファイルのenvironmentsに定義された名前を指定する
+    #[arg(short, long)]
+    env: Option<String>,
+
+    /// データ生成に使用する乱数のシード
+    /// 指定した場合は、同じシードで同じ値を生成する
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// テストが失敗した場合に、リクエストを再現するcurlコマンドを表示するかどうか
+    #[arg(long, default_value = "false")]
+    curl_on_failure: bool,
+
+    /// curlコマンドに含まれる秘匿情報(認証ヘッダ、クッキー、パスワードなど)を伏せるかどうか
+    #[arg(long, default_value = "false")]
+    redact: bool,
+
+    /// リクエストとレスポンスを記録するカセットファイルのパス
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<String>,
+
+    /// ネットワークに送信せず、レスポンスを返すカセットファイルのパス
+    #[arg(long)]
+    replay: Option<String>,
+}
+
+// サブコマンドを定義
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 他の形式からテスト構成ファイルとデータファイルを生成する
+    #[command(subcommand)]
+    Import(ImportCommand),
+    /// テスト構成ファイルを他の形式に変換する
+    #[command(subcommand)]
+    Export(ExportCommand),
+    /// テスト構成ファイルのステップをモックサーバーとして提供する
+    Mock {
+        /// インプットするymlファイルのパス
+        #[arg(short, long)]
+        input_yml_path: String,
+
+        /// 使用する環境の名前
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// データ生成に使用する乱数のシード
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// 待ち受けるホスト
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// 待ち受けるポート
+        #[arg(short, long, default_value = "8080")]
+        port: u16,
+    },
+}
+
+// エクスポート先の形式を定義
+#[derive(Subcommand, Debug)]
+enum ExportCommand {
+    /// 全てのステップをcurlコマンドに変換する
+    Curl {
+        /// インプットするymlファイルのパス
+        #[arg(short, long)]
+        input_yml_path: String,
+
+        /// 出力先のファイルのパス
+        /// 指定がない場合は標準出力に表示する
+        #[arg(short, long)]
+        output_path: Option<String>,
+
+        /// 使用する環境の名前
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// データ生成に使用する乱数のシード
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// initステップを実行し、取得したクッキーをコマンドに含めるかどうか
+        #[arg(short, long, default_value = "false")]
+        login: bool,
+
+        /// 秘匿情報(認証ヘッダ、クッキー、パスワードなど)を伏せるかどうか
+        #[arg(long, default_value = "false")]
+        redact: bool,
+    },
+}
+
+// インポート元の形式を定義
+#[derive(Subcommand, Debug)]
+enum ImportCommand {
+    /// OpenAPIドキュメント(3.x)から生成する
+    Openapi {
+        #[command(flatten)]
+        import_args: ImportArgs,
+
+        /// テスト構成ファイルに記述するベースURL
+        /// 指定がない場合はドキュメントの最初のサーバーを使用する
+        #[arg(short, long)]
+        server_url: Option<String>,
+    },
+    /// Postmanのコレクション(v2.1)から生成する
+    Postman {
+        #[command(flatten)]
+        import_args: ImportArgs,
+
+        /// 変数として読み込むPostmanの環境ファイルのパス
+        #[arg(short, long)]
+        environment: Option<String>,
+    },
+    /// ブラウザやプロキシで記録したHARファイルから生成する
+    Har {
+        #[command(flatten)]
+        import_args: ImportArgs,
+
+        /// 対象とするホスト名。複数指定できる
+        /// 指定がない場合は全てのホストを対象とする
+        #[arg(long)]
+        host: Vec<String>,
+
+        /// 対象とするパスの接頭辞
+        #[arg(long)]
+        path_prefix: Option<String>,
+    },
+}
+
+// インポートの共通の引数を定義
+#[derive(ClapArgs, Debug)]
+struct ImportArgs {
+    /// インポートするファイルのパス
+    input_path: String,
+
+    /// 出力するテスト構成ファイルのパス
+    #[arg(short, long, default_value = "raxtest.yml")]
+    output_yml_path: String,
+
+    /// 出力するデータファイルのパス
+    #[arg(short = 'd', long, default_value = "data.json")]
+    output_data_path: String,
 }
 
 #[tokio::main]
@@ -40,34 +197,158 @@ async fn main() -> AppResult<()> {
 "#;
     println!("{}", ascii_art);
 
-    let print_flag = args.print_flag;
-    let cookie_error_continue = args.cookie_error_continue;
+    // モックのサブコマンドが指定された場合は、モックサーバーを起動する
+    if let Some(Command::Mock {
+        input_yml_path,
+        env,
+        seed,
+        host,
+        port,
+    }) = args.command
+    {
+        let (test_config, json_data) = gen_struct(input_yml_path, env.as_deref(), seed)?;
+        let addr = format!("{}:{}", host, port)
+            .parse()
+            .with_context(|| format!("Invalid address: {}:{}", host, port))?;
+        run_mock(&test_config, &json_data, addr).await?;
+        return Ok(());
+    }
+
+    // エクスポートのサブコマンドが指定された場合は、curlコマンドを出力する
+    if let Some(Command::Export(ExportCommand::Curl {
+        input_yml_path,
+        output_path,
+        env,
+        seed,
+        login,
+        redact,
+    })) = args.command
+    {
+        let (test_config, json_data) = gen_struct(input_yml_path, env.as_deref(), seed)?;
+
+        // ログインする場合は、initステップを実行してクッキーを取得する
+        let cookie_map = if login {
+            println!("[*] Initializing HTTP client...");
+            run_init(&test_config, &json_data, &HttpClient::network(), &false).await?
+        } else {
+            HashMap::new()
+        };
+
+        let commands = export_curl(&test_config, &json_data, &cookie_map, redact)?;
+        match output_path {
+            Some(output_path) => {
+                println!("[*] Writing curl commands... -> [{}]", output_path);
+                std::fs::write(&output_path, commands)
+                    .with_context(|| format!("Failed to write file: {}", output_path))?;
+            }
+            None => println!("{}", commands),
+        }
+        println!("[*] Export completed!");
+        return Ok(());
+    }
+
+    // インポートのサブコマンドが指定された場合はサブコマンドを実行する
+    if let Some(Command::Import(import_command)) = args.command {
+        let (test_config, data_map, import_args) = match import_command {
+            ImportCommand::Openapi {
+                import_args,
+                server_url,
+            } => {
+                let (test_config, data_map) = import_openapi(
+                    &import_args.input_path,
+                    server_url.as_deref(),
+                    &import_args.output_yml_path,
+                    &import_args.output_data_path,
+                )?;
+                (test_config, data_map, import_args)
+            }
+            ImportCommand::Postman {
+                import_args,
+                environment,
+            } => {
+                let (test_config, data_map) = import_postman(
+                    &import_args.input_path,
+                    environment.as_deref(),
+                    &import_args.output_yml_path,
+                    &import_args.output_data_path,
+                )?;
+                (test_config, data_map, import_args)
+            }
+            ImportCommand::Har {
+                import_args,
+                host,
+                path_prefix,
+            } => {
+                let (test_config, data_map) = import_har(
+                    &import_args.input_path,
+                    &HarFilter {
+                        hosts: host,
+                        path_prefix,
+                    },
+                    &import_args.output_yml_path,
+                    &import_args.output_data_path,
+                )?;
+                (test_config, data_map, import_args)
+            }
+        };
+        write_suite(
+            &test_config,
+            &data_map,
+            &import_args.output_yml_path,
+            &import_args.output_data_path,
+        )?;
+        println!("[*] Import completed!");
+        return Ok(());
+    }
+
+    // 必須の引数はclapで検証済み
+    let input_yml_path = args.input_yml_path.expect("input_yml_path is required");
+    let output_json_path = args.output_json_path.expect("output_json_path is required");
+
+    let options = RunOptions {
+        print_flag: args.print_flag,
+        cookie_error_continue: args.cookie_error_continue,
+        curl_on_failure: args.curl_on_failure,
+        redact: args.redact,
+    };
+
+    // HTTPクライアントを初期化
+    // カセットファイルが指定された場合は、記録またはリプレイする
+    println!("[*] Initializing HTTP client...");
+    let client = match (&args.record, &args.replay) {
+        (_, Some(replay)) => HttpClient::replay(replay)?,
+        (Some(_), None) => HttpClient::record(args.seed),
+        (None, None) => HttpClient::network(),
+    };
 
     // テスト構成ファイルの構造体を生成する
-    let (test_config, json_data) = gen_struct(args.input_yml_path)?;
-
-    // initステップを実行し、クッキーを取得する
-    let cookie_map = run_init(
-        &test_config.base_url,
-        test_config.init,
-        &json_data,
-        &print_flag,
-    )
-    .await?;
-
-    // テストステップを実行する
-    let results = run_test(
-        &test_config.base_url,
-        test_config.categories,
-        &json_data,
-        &cookie_map,
-        &print_flag,
-        &cookie_error_continue,
-    )
-    .await?;
+    // 記録とリプレイでは、カセットファイルのシードと時刻でデータを生成し、同じリクエストを送信する
+    let seed = client.seed().or(args.seed);
+    let (test_config, json_data) = gen_struct(input_yml_path, args.env.as_deref(), seed)?;
+    if let Some(now) = client.now() {
+        test_config.generator.lock().unwrap().fix_now(now);
+    }
+
+    let outcome: AppResult<_> = async {
+        // initステップを実行し、クッキーを取得する
+        let cookie_map = run_init(&test_config, &json_data, &client, &options.print_flag).await?;
+
+        // テストステップを実行する
+        run_test(&test_config, &json_data, &cookie_map, &client, &options).await
+    }
+    .await;
+
+    // 記録したリクエストとレスポンスを書き出す
+    // テストが途中で中断した場合も、それまでに記録したものを書き出す
+    let saved = match &args.record {
+        Some(record) => client.save(record),
+        None => Ok(()),
+    };
+    let (results, coverage) = outcome?;
+    saved?;
 
     // テスト結果をレンダリング
-    render_results(&test_config.base_url, &args.output_json_path, results)?;
+    render_results(&test_config.base_url, &output_json_path, results, coverage)?;
 
     Ok(())
 }
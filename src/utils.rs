@@ -1,127 +1,726 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use futures::future::join_all;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use regex::Regex;
-use reqwest::{Client, Error, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+use reqwest::{Response, Url};
 use serde_json::{to_writer_pretty, Value};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::BufReader;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use std::{collections::HashMap, str::FromStr};
 use tokio::task::JoinHandle;
 
+pub mod assertion;
+pub mod body;
+pub mod cassette;
+pub mod config;
+pub mod contract;
+pub mod coverage;
+pub mod curl;
+pub mod data;
+pub mod env;
+pub mod generator;
+pub mod graphql;
+pub mod import;
+pub mod mock;
+pub mod openapi;
+pub mod schema;
+pub mod stream;
 pub mod types;
+pub mod websocket;
 use types::{
-    AppResult, InputCaterogy, InputConfigration, InputData, InputDataMap, InputStep, OutputData,
-    OutputResult,
+    AppResult, InputCaterogy, InputConfigration, InputData, InputDataMap, InputMatrix,
+    MaxDurationMode, OutputCoverage, OutputData, OutputResult, RunOptions,
 };
 
+use self::assertion::compile_body_assertion;
+use self::body::{gen_request_body, RequestBody};
+use self::cassette::HttpClient;
+use self::config::{expand_config_env, load_config_value, resolve_templates};
+use self::contract::Contract;
+use self::coverage::CoverageTracker;
+use self::curl::to_curl;
+use self::data::{insert_data, load_data_source, DATA_SCHEMES};
+use self::env::apply_environment;
+use self::generator::{check_generators, Generator};
+use self::graphql::{compile_graphql_assertion, gen_graphql_body};
+use self::schema::{compile_schema, load_schemas, validate_body};
+use self::stream::gen_stream_assertion;
 use self::types::FlattenStep;
+use self::websocket::{gen_websocket_scenario, run_websocket};
+
+// テストステップのタスクの型
+// WebSocketとストリーミングのステップの場合は、メッセージとイベントの検証に失敗した内容を含む
+type TestTask = JoinHandle<AppResult<(String, Response, Duration, Vec<String>)>>;
+
+// プレースホルダ {key} にマッチする正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn placeholder_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{(\w+)\}").unwrap())
+}
+
+// 文字列中のプレースホルダ {key} を変数の値で置換する関数
+// 引数
+// - template: &str -> 置換対象の文字列。不変参照
+// - vars: &HashMap<String, Value> -> 置換に使用する変数の連想配列。不変参照
+// 戻り値：String -> 置換後の文字列。対応する変数が存在しないプレースホルダはそのまま残す
+pub fn interpolate(template: &str, vars: &HashMap<String, Value>) -> String {
+    placeholder_regex()
+        .replace_all(template, |captures: &regex::Captures| {
+            match vars.get(&captures[1]) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Number(n)) => n.to_string(),
+                Some(_) => "".to_string(),
+                None => captures[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+// 置換に使用する変数の連想配列を生成する関数
+// 引数
+// - variables: &HashMap<String, String> -> テスト構成ファイルの変数。不変参照
+// - query: Option<&HashMap<String, Value>> -> データのクエリ。変数より優先される
+// 戻り値：HashMap<String, Value> -> 置換に使用する変数の連想配列
+pub fn gen_variables(
+    variables: &HashMap<String, String>,
+    query: Option<&HashMap<String, Value>>,
+) -> HashMap<String, Value> {
+    let mut vars: HashMap<String, Value> = variables
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+        .collect();
+    if let Some(query) = query {
+        vars.extend(query.clone());
+    }
+    vars
+}
+
+// サービス名からリクエスト先のベースURLを解決する関数
+// 引数
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// - service: Option<&String> -> サービス名。指定がない場合はルートのbase_urlを使用する
+// 戻り値：AppResult<&str> -> ベースURLをAppResultでラップしたもの
+pub fn resolve_base_url<'a>(
+    test_config: &'a InputConfigration,
+    service: Option<&String>,
+) -> AppResult<&'a str> {
+    match service {
+        Some(service) => test_config
+            .services
+            .get(service)
+            .map(|base_url| base_url.as_str())
+            .ok_or_else(|| anyhow!("Service not found: {}", service)),
+        None if test_config.base_url.is_empty() => Err(anyhow!("base_url is not specified")),
+        None => Ok(&test_config.base_url),
+    }
+}
+
+// カテゴリをカテゴリ名の順に返す関数
+// データ生成の値をシードで再現できるように、ステップは常に同じ順序でフラットにする
+// 引数：test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// 戻り値：Vec<(&String, &InputCaterogy)> -> カテゴリ名とカテゴリの組の配列
+pub fn sorted_categories(test_config: &InputConfigration) -> Vec<(&String, &InputCaterogy)> {
+    let mut categories: Vec<(&String, &InputCaterogy)> = test_config.categories.iter().collect();
+    categories.sort_by_key(|(name, _)| *name);
+    categories
+}
+
+// ベースURLのパス部分を返す関数
+// サービスごとのベースURLのパス(例: http://host/auth -> /auth)を、リクエストのパスの接頭辞として扱う
+// 引数：base_url: &str -> ベースURL
+// 戻り値：String -> 末尾のスラッシュを除いたパス
+pub fn base_path(base_url: &str) -> String {
+    Url::parse(base_url)
+        .map(|url| url.path().trim_end_matches('/').to_string())
+        .unwrap_or_default()
+}
+
+// データのヘッダの連想配列を生成する関数
+// content_typeが指定された場合は、Content-Typeヘッダとして追加する
+// 引数：data: &InputData -> データ。不変参照
+// 戻り値：HashMap<String, String> -> データのヘッダの連想配列
+pub fn gen_data_headers(data: &InputData) -> HashMap<String, String> {
+    let mut headers = data.headers.clone().unwrap_or_default();
+    if let Some(content_type) = &data.content_type {
+        // 大文字小文字の異なる同名のヘッダは、content_typeで置き換える
+        headers.retain(|key, _| !key.eq_ignore_ascii_case("content-type"));
+        headers.insert("Content-Type".to_string(), content_type.clone());
+    }
+    headers
+}
+
+// 複数のヘッダの連想配列をマージしてHeaderMapを生成する関数
+// 引数
+// - layers: &[&HashMap<String, String>] -> ヘッダの連想配列のスライス。後ろにあるものほど優先される
+// - vars: &HashMap<String, Value> -> ヘッダの値の置換に使用する変数の連想配列。不変参照
+// 戻り値：AppResult<HeaderMap> -> マージしたヘッダをAppResultでラップしたもの
+pub fn gen_headers(
+    layers: &[&HashMap<String, String>],
+    vars: &HashMap<String, Value>,
+) -> AppResult<HeaderMap> {
+    let mut headers = HeaderMap::new();
+
+    for layer in layers {
+        for (key, value) in layer.iter() {
+            let name =
+                HeaderName::from_str(key).map_err(|_| anyhow!("Invalid header name: {}", key))?;
+            let value = HeaderValue::from_str(&interpolate(value, vars))
+                .map_err(|_| anyhow!("Invalid header value: {}", key))?;
+            // HeaderMapのキーは大文字小文字を区別しないため、同名のヘッダは上書きされる
+            headers.insert(name, value);
+        }
+    }
+
+    Ok(headers)
+}
 
 // テスト構成ファイルの構造体を生成する関数
-// 引数：index_path: String -> テスト構成ファイルのパス。所有権を移動する
+// 引数
+// - index_path: String -> テスト構成ファイルのパス。所有権を移動する
+// - env_name: Option<&str> -> 適用する環境の名前。不変参照
+// - seed: Option<u64> -> データ生成に使用する乱数のシード
 // 戻り値：RaxResult<(TestConfig, JsonMap)> -> テスト構成ファイルの構造体とjsonデータの連想配列のタプル
-pub fn gen_struct(index_path: String) -> AppResult<(InputConfigration, InputDataMap)> {
+pub fn gen_struct(
+    index_path: String,
+    env_name: Option<&str>,
+    seed: Option<u64>,
+) -> AppResult<(InputConfigration, InputDataMap)> {
     // テスト構成ファイルを読み込む
     println!("[*] Loading test config file...");
-    let config_file = File::open(&index_path)?;
-    let reader = BufReader::new(config_file);
-    let test_config: InputConfigration = serde_yaml::from_reader(reader)?;
+    let config_path = std::path::absolute(&index_path)?;
+    // includeされたファイルをマージし、テンプレートを展開する
+    // データファイルごとに、そのデータファイルを宣言したファイルのディレクトリを記録する
+    let mut data_dirs: HashMap<String, PathBuf> = HashMap::new();
+    let mut config_value = load_config_value(&config_path, &mut Vec::new(), &mut data_dirs)?;
+    resolve_templates(&mut config_value)?;
+    // 選択された環境以外を取り除いてから、文字列に含まれる環境変数を展開する
+    expand_config_env(&mut config_value, env_name)?;
+    let mut test_config: InputConfigration = serde_yaml::from_value(config_value)
+        .with_context(|| format!("Failed to parse config file: {}", config_path.display()))?;
+    // 相対パスはテスト構成ファイルのディレクトリを基準に解決する
+    test_config.base_dir = config_path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default();
+
+    // 環境が指定された場合は、環境の設定を適用する
+    if let Some(env_name) = env_name {
+        println!("[*] Applying environment... -> [{}]", env_name);
+        apply_environment(&mut test_config, env_name)?;
+    }
+
+    // 全てのステップのリクエスト先が解決できるかチェックする
+    for init_step in test_config.init.iter() {
+        resolve_base_url(&test_config, init_step.service.as_ref())?;
+    }
+    for category in test_config.categories.values() {
+        for step in category.steps.iter() {
+            resolve_base_url(
+                &test_config,
+                step.service.as_ref().or(category.service.as_ref()),
+            )?;
+        }
+    }
 
     // データファイルのパス指定が正しいかチェックする
     println!("[*] Checking data file path...");
 
-    // jsonデータのパスが正しいかチェックする
-    if !test_config.data.starts_with("json://") {
-        return Err(anyhow!("Invalid data file path"));
-        // 代替案: bail!("Invalid data file path"); -> anyhowのマクロ anyhowのエラーを返す
-        // 代替案2: ensure!(test_config.data.starts_with("json://"),"Invalid data file path"); -> ensureマクロ ifをつかわずに書ける
+    // データファイルのスキームが正しいかチェックする
+    if let Some(source) = test_config
+        .data
+        .iter()
+        .find(|source| !DATA_SCHEMES.iter().any(|scheme| source.starts_with(scheme)))
+    {
+        return Err(anyhow!("Invalid data file path: {}", source));
+    }
+
+    // データファイルを読み込み、一つの連想配列にマージする
+    let mut json_data: InputDataMap = HashMap::new();
+    for source in test_config.data.iter() {
+        println!("[*] Loading data file... -> [{}]", source);
+        let data_dir = data_dirs
+            .get(source)
+            .cloned()
+            .unwrap_or_else(|| test_config.base_dir.clone());
+        for (key, mut data) in load_data_source(source, &data_dir)? {
+            // データファイルに書かれたパスは、データファイルを宣言したファイルのディレクトリを基準に解決する
+            for data in data.iter_mut() {
+                data.base_dir = Some(data_dir.clone());
+            }
+            insert_data(&mut json_data, key, data)?;
+        }
+    }
+
+    // ステップに直接記述されたデータを連想配列に追加する
+    // ref_dataが省略されている場合は、カテゴリ名とステップ名をキーにする
+    for init_step in test_config.init.iter_mut() {
+        if let Some(data) = init_step.data.take() {
+            if init_step.ref_data.is_empty() {
+                init_step.ref_data = format!("init/{}", init_step.name);
+            }
+            insert_data(&mut json_data, init_step.ref_data.clone(), data)?;
+        }
+    }
+    for (category_name, category) in test_config.categories.iter_mut() {
+        for step in category.steps.iter_mut() {
+            if let Some(data) = step.data.take() {
+                if step.ref_data.is_empty() {
+                    step.ref_data = format!("{}/{}", category_name, step.name);
+                }
+                insert_data(&mut json_data, step.ref_data.clone(), data)?;
+            }
+        }
+    }
+
+    // マトリクスの指定が正しいかチェックする
+    if let Some(init_step) = test_config.init.iter().find(|s| !s.matrix.is_empty()) {
+        return Err(anyhow!(
+            "matrix is not supported for init steps -> [{}]",
+            init_step.name
+        ));
+    }
+    // initステップはクッキーの取得に使用するため、WebSocketとストリーミングに対応しない
+    if let Some(init_step) = test_config.init.iter().find(|s| s.websocket.is_some()) {
+        return Err(anyhow!(
+            "websocket is not supported for init steps -> [{}]",
+            init_step.name
+        ));
+    }
+    if let Some(init_step) = test_config.init.iter().find(|s| s.stream.is_some()) {
+        return Err(anyhow!(
+            "stream is not supported for init steps -> [{}]",
+            init_step.name
+        ));
+    }
+    for (category_name, category) in test_config.categories.iter() {
+        for (key, values) in category
+            .matrix
+            .iter()
+            .chain(category.steps.iter().flat_map(|s| s.matrix.iter()))
+        {
+            if values.is_empty() {
+                return Err(anyhow!("Empty matrix: {} -> [{}]", key, category_name));
+            }
+        }
+    }
+
+    // 全てのステップの参照するデータが存在するかチェックする
+    for step in test_config
+        .init
+        .iter()
+        .chain(test_config.categories.values().flat_map(|c| c.steps.iter()))
+    {
+        if !json_data.contains_key(&step.ref_data) {
+            return Err(anyhow!(
+                "Data not found: {} -> [{}]",
+                step.ref_data,
+                step.name
+            ));
+        }
     }
 
-    // データの格納されているjsonファイルを読み込む
-    println!("[*] Loading json data file...");
-    let data_file = File::open(test_config.data.trim_start_matches("json://"))?;
-    let reader = BufReader::new(data_file);
-    let json_data: InputDataMap = serde_json::from_reader(reader)?;
+    // レスポンスボディを検証するスキーマを読み込む
+    load_schemas(&mut test_config, &mut json_data)?;
+
+    // 契約を検証するOpenAPIドキュメントを読み込む
+    if let Some(openapi) = test_config.openapi.as_ref() {
+        let openapi_path = test_config.base_dir.join(openapi);
+        println!(
+            "[*] Loading OpenAPI document... -> [{}]",
+            openapi_path.display()
+        );
+        test_config.contract = Some(Contract::load(&openapi_path)?);
+    }
+
+    // データとヘッダの {{ expr }} が評価できるかチェックする
+    // 値はフラットされたステップごとに生成する
+    check_generators(&test_config, &json_data)?;
+    test_config.generator = Mutex::new(Generator::new(seed));
 
     // 成功として、テスト構成ファイルの構造体とjsonデータを返す
     Ok((test_config, json_data))
 }
 
 // フラットされたステップの構造体を生成する関数
-// 引数：test_config: Vec<InputStep> -> テスト構成ファイルの構造体。不変参照
+// 引数
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// - category: &InputCaterogy -> ステップを含むカテゴリの構造体。不変参照
+// - input_data_map: &InputDataMap -> jsonデータの連想配列。不変参照
 // 戻り値：RaxResult<Vec<(index, step_index, data_index, FlattenStep)>> -> フラットされたステップの構造体の配列をRaxResultでラップしたもの
 pub fn gen_flatten_step(
-    test_steps: &Vec<InputStep>,
+    test_config: &InputConfigration,
+    category: &InputCaterogy,
     input_data_map: &InputDataMap,
 ) -> AppResult<Vec<(usize, usize, FlattenStep)>> {
     let mut flatten_steps: Vec<(usize, usize, FlattenStep)> = Vec::new();
 
-    for (step_index, step) in test_steps.iter().enumerate() {
-        // データの数だけステップを複製する
-        for (data_index, data) in (input_data_map
-            .get(&step.ref_data)
-            .expect("Invalid data reference"))
-        .iter()
-        .enumerate()
-        {
-            // データオブジェクトの作成
-            let input_data = InputData {
-                // もしオプションでtrueが指定されたらボディを読み込む
-                // Option型でそのまま渡される
-                body: if step.option.body {
-                    data.body.clone()
-                } else {
-                    None
-                },
-                // もしオプションでtrueが指定されたらクエリを読み込む
-                // Option型でそのまま渡される
-                query: if step.option.query {
-                    data.query.clone()
-                } else {
-                    None
-                },
-                expect_status: data.expect_status,
-            };
+    for (step_index, step) in category.steps.iter().enumerate() {
+        // ステップのサービス、カテゴリのサービスの順にベースURLを解決する
+        let base_url = resolve_base_url(
+            test_config,
+            step.service.as_ref().or(category.service.as_ref()),
+        )?;
+
+        // カテゴリとステップのマトリクスをマージし、パラメータの組み合わせの数だけステップを複製する
+        let mut matrix = category.matrix.clone();
+        matrix.extend(step.matrix.clone());
+
+        for combination in gen_matrix_combinations(&matrix) {
+            // マトリクスのパラメータは変数として置換に使用する
+            let mut variables = test_config.variables.clone();
+            variables.extend(
+                combination
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value_to_string(value))),
+            );
+            let variables = &variables;
 
-            let flatten_step = FlattenStep {
-                name: format!("{}[{}]", step.name, data_index),
-                method: step.method.clone(),
-                path: step.path.clone(),
-                input_data: input_data,
+            // パラメータがある場合はステップ名に含める
+            let step_name = if combination.is_empty() {
+                step.name.clone()
+            } else {
+                format!(
+                    "{}[{}]",
+                    step.name,
+                    combination
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value_to_string(value)))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                )
             };
-            // フラットされたステップを配列に追加する
-            flatten_steps.push((step_index, data_index, flatten_step));
+
+            // データの数だけステップを複製する
+            for (data_index, data) in (input_data_map
+                .get(&step.ref_data)
+                .expect("Invalid data reference"))
+            .iter()
+            .enumerate()
+            {
+                // データとヘッダの {{ expr }} は、マトリクスの組み合わせとデータごとに評価する
+                let mut data = data.clone();
+                let mut step_headers = [
+                    test_config.headers.clone(),
+                    category.headers.clone(),
+                    step.headers.clone(),
+                ];
+                {
+                    let mut generator = test_config.generator.lock().unwrap();
+                    generator.render_data(&mut data)?;
+                    for headers in step_headers.iter_mut() {
+                        generator.render_headers(headers)?;
+                    }
+                }
+                let data = &data;
+                let [root_headers, category_headers, step_headers] = &step_headers;
+
+                // データ > ステップの順にボディの形式を解決する
+                let body_type = data.body_type.or(step.body_type).unwrap_or_default();
+
+                // データオブジェクトの作成
+                let input_data = InputData {
+                    // もしオプションでtrueが指定されたらボディを読み込む
+                    // Option型でそのまま渡される
+                    body: if step.option.body {
+                        data.body.clone()
+                    } else {
+                        None
+                    },
+                    // もしオプションでtrueが指定されたらクエリを読み込む
+                    // Option型でそのまま渡される
+                    query: if step.option.query {
+                        data.query.clone()
+                    } else {
+                        None
+                    },
+                    headers: data.headers.clone(),
+                    expect_status: data.expect_status.clone(),
+                    max_duration_ms: data.max_duration_ms,
+                    expect_schema: None,
+                    mock_response: data.mock_response.clone(),
+                    body_type: Some(body_type),
+                    raw_body: data.raw_body.clone().filter(|_| step.option.body),
+                    body_file: data.body_file.clone().filter(|_| step.option.body),
+                    content_type: data.content_type.clone(),
+                    expect_body: None,
+                    variables: data.variables.clone(),
+                    expect_errors: data.expect_errors,
+                    expect_data: data.expect_data.clone(),
+                    messages: data.messages.clone(),
+                    expect_events: data.expect_events.clone(),
+                    base_dir: data.base_dir.clone(),
+                };
+
+                // パスとヘッダの {key} は、変数とオプションで読み込んだデータのクエリの値で置換する
+                let step_variables = gen_variables(variables, input_data.query.as_ref());
+
+                // ヘッダをルート < カテゴリ < ステップ < データの優先順位でマージする
+                let data_headers = gen_data_headers(data);
+                let headers = gen_headers(
+                    &[root_headers, category_headers, step_headers, &data_headers],
+                    &step_variables,
+                )?;
+
+                let flatten_step = FlattenStep {
+                    name: format!("{}[{}]", step_name, data_index),
+                    base_url: base_url.to_string(),
+                    method: step.method.clone(),
+                    path: step.path.clone(),
+                    headers,
+                    variables: step_variables,
+                    // データ > ステップ > カテゴリ > ルートの順に所要時間の上限を解決する
+                    max_duration: data
+                        .max_duration_ms
+                        .or(step.max_duration_ms)
+                        .or(category.max_duration_ms)
+                        .or(test_config.max_duration_ms)
+                        .map(Duration::from_millis),
+                    // データ > ステップの順にスキーマを解決する
+                    expect_schema: data
+                        .expect_schema
+                        .as_ref()
+                        .or(step.expect_schema.as_ref())
+                        .map(|schema| compile_schema(schema).map(Arc::new))
+                        .transpose()?,
+                    // データ > ステップの順にレスポンスボディの検証を解決する
+                    expect_body: data
+                        .expect_body
+                        .as_ref()
+                        .or(step.expect_body.as_ref())
+                        .map(compile_body_assertion)
+                        .transpose()
+                        .with_context(|| {
+                            format!("Invalid expect_body: {}[{}]", step_name, data_index)
+                        })?,
+                    // GraphQLのステップの場合は、レスポンスのerrorsとdataを検証する
+                    graphql: step
+                        .graphql
+                        .as_ref()
+                        .map(|_| compile_graphql_assertion(data))
+                        .transpose()
+                        .with_context(|| {
+                            format!("Invalid expect_data: {}[{}]", step_name, data_index)
+                        })?,
+                    // WebSocketのステップの場合は、データのメッセージからシナリオを生成する
+                    websocket: step
+                        .websocket
+                        .as_ref()
+                        .map(|websocket| gen_websocket_scenario(websocket, data))
+                        .transpose()
+                        .with_context(|| {
+                            format!("Invalid messages: {}[{}]", step_name, data_index)
+                        })?,
+                    // ストリーミングのステップの場合は、読み込む範囲とデータのイベントの検証を生成する
+                    stream: step
+                        .stream
+                        .as_ref()
+                        .map(|stream| gen_stream_assertion(stream, data))
+                        .transpose()
+                        .with_context(|| {
+                            format!("Invalid stream: {}[{}]", step_name, data_index)
+                        })?,
+                    // GraphQLのステップの場合はクエリと変数を、
+                    // それ以外でオプションでtrueが指定された場合は、ボディの形式に従ってボディを生成する
+                    request_body: match &step.graphql {
+                        Some(graphql) => {
+                            Some(gen_graphql_body(graphql, Some(data), &test_config.base_dir))
+                                .transpose()
+                        }
+                        None if step.option.body => {
+                            gen_request_body(body_type, data, &test_config.base_dir)
+                        }
+                        None => Ok(None),
+                    }
+                    .with_context(|| {
+                        format!("Invalid request body: {}[{}]", step_name, data_index)
+                    })?,
+                    input_data,
+                };
+                // フラットされたステップを配列に追加する
+                flatten_steps.push((step_index, data_index, flatten_step));
+            }
         }
     }
     Ok(flatten_steps)
 }
 
+// マトリクスのパラメータの全ての組み合わせを生成する関数
+// 引数：matrix: &InputMatrix -> マトリクス。不変参照
+// 戻り値：Vec<Vec<(String, Value)>> -> パラメータ名と値の組の配列の配列。マトリクスが空の場合は空の組み合わせを一つ返す
+pub fn gen_matrix_combinations(matrix: &InputMatrix) -> Vec<Vec<(String, Value)>> {
+    matrix
+        .iter()
+        .fold(vec![Vec::new()], |combinations, (key, values)| {
+            combinations
+                .iter()
+                .flat_map(|combination| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push((key.clone(), value.clone()));
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+// jsonの値が予期した値を含むかどうかを判定する関数
+// オブジェクトは予期した値のキーのみを再帰的に比較し、それ以外は一致を比較する
+// 引数
+// - actual: &Value -> 実際の値。不変参照
+// - expect: &Value -> 予期した値。不変参照
+// 戻り値：bool -> 含む場合はtrue
+pub fn json_includes(actual: &Value, expect: &Value) -> bool {
+    match (actual, expect) {
+        (Value::Object(actual), Value::Object(expect)) => expect.iter().all(|(key, value)| {
+            actual
+                .get(key)
+                .is_some_and(|actual| json_includes(actual, value))
+        }),
+        _ => actual == expect,
+    }
+}
+
+// jsonの値を文字列に変換する関数
+// 引数：value: &Value -> 変換する値。不変参照
+// 戻り値：String -> 文字列。文字列の場合は引用符を付けない
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+// 連想配列を、キーの順に並べたjsonのオブジェクトに変換する関数
+// serde_jsonのMapは追加した順に並ぶため、HashMapの順序に依存しないようにソートする
+// 引数：map: &HashMap<String, Value> -> 変換する連想配列。不変参照
+// 戻り値：Value -> jsonのオブジェクト
+pub fn sorted_object(map: &HashMap<String, Value>) -> Value {
+    let sorted: BTreeMap<&String, &Value> = map.iter().collect();
+    Value::Object(
+        sorted
+            .into_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    )
+}
+
 // initステップを実行する関数
 // 引数
-// - base_url: &String -> テスト対象のベースURL。不変参照
-// - init: Vec<InitStep> -> initステップの構造体の配列。所有権を移動する
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
 // - json_data: &JsonMap -> jsonデータの連想配列。不変参照
+// - client: &HttpClient -> テストステップと共有するHTTPクライアント。不変参照
 // 戻り値：RaxResult<HashMap<String, String>> -> クッキーの連想配列をRaxResultでラップしたもの
 
 pub async fn run_init(
-    base_url: &String,
-    init_steps: Vec<InputStep>,
+    test_config: &InputConfigration,
     input_data_map: &InputDataMap,
+    client: &HttpClient,
     print_flag: &bool,
 ) -> AppResult<HashMap<String, String>> {
+    let init_steps = &test_config.init;
+    let variables = &test_config.variables;
+    let headers = &test_config.headers;
+
     // クッキーを格納するハッシュマップを初期化
     let mut cookie_map: HashMap<String, String> = HashMap::new();
 
-    // HTTPクライアントを初期化
-    println!("[*] Initializing HTTP client...");
-    let client = Client::new();
+    // initステップごとのデータとヘッダの {{ expr }} を評価する
+    // テストステップと同じ生成器を使用し、initステップの順に評価する
+    let mut init_data_list: Vec<Option<InputData>> = Vec::new();
+    let mut init_step_headers: Vec<[HashMap<String, String>; 2]> = Vec::new();
+    {
+        let mut generator = test_config.generator.lock().unwrap();
+        for init_step in init_steps.iter() {
+            let mut init_data = input_data_map
+                .get(&init_step.ref_data)
+                .unwrap()
+                .first()
+                .cloned();
+            if let Some(init_data) = init_data.as_mut() {
+                generator.render_data(init_data)?;
+            }
+            let mut step_headers = [headers.clone(), init_step.headers.clone()];
+            for headers in step_headers.iter_mut() {
+                generator.render_headers(headers)?;
+            }
+            init_data_list.push(init_data);
+            init_step_headers.push(step_headers);
+        }
+    }
+
+    // initステップごとに、パスとヘッダの {key} の置換に使用する変数を生成する
+    // テストステップと同じく、オプションでtrueが指定された場合はデータのクエリの値で置換する
+    let init_variables = init_steps
+        .iter()
+        .zip(init_data_list.iter())
+        .map(|(init_step, init_data)| {
+            gen_variables(
+                variables,
+                init_data
+                    .as_ref()
+                    .filter(|_| init_step.option.query)
+                    .and_then(|data| data.query.as_ref()),
+            )
+        })
+        .collect::<Vec<HashMap<String, Value>>>();
+
+    // initステップごとのヘッダを事前に生成する
+    // ヘッダはルート < ステップ < データの優先順位でマージする
+    let init_headers = init_data_list
+        .iter()
+        .zip(init_step_headers.iter())
+        .zip(init_variables.iter())
+        .map(|((init_data, [root_headers, step_headers]), vars)| {
+            let data_headers = init_data.as_ref().map(gen_data_headers).unwrap_or_default();
+            gen_headers(&[root_headers, step_headers, &data_headers], vars)
+        })
+        .collect::<AppResult<Vec<HeaderMap>>>()?;
+
+    // initステップごとのリクエストボディを事前に生成する
+    // オプションのリクエストボディフラグがtrue, かつ、対応するデータが存在する場合に生成する
+    let init_bodies = init_steps
+        .iter()
+        .zip(init_data_list.iter())
+        .map(|(init_step, init_data)| {
+            let init_data = init_data.as_ref();
+            if let Some(graphql) = &init_step.graphql {
+                return gen_graphql_body(graphql, init_data, &test_config.base_dir)
+                    .map(Some)
+                    .with_context(|| format!("Invalid request body: init/{}", init_step.name));
+            }
+            match init_data.filter(|_| init_step.option.body) {
+                Some(init_data) => gen_request_body(
+                    init_data
+                        .body_type
+                        .or(init_step.body_type)
+                        .unwrap_or_default(),
+                    init_data,
+                    &test_config.base_dir,
+                )
+                .with_context(|| format!("Invalid request body: init/{}", init_step.name)),
+                None => Ok(None),
+            }
+        })
+        .collect::<AppResult<Vec<Option<RequestBody>>>>()?;
+
+    // initステップごとのリクエスト先のベースURLを解決する
+    let init_base_urls = init_steps
+        .iter()
+        .map(|init_step| resolve_base_url(test_config, init_step.service.as_ref()))
+        .collect::<AppResult<Vec<&str>>>()?;
 
     // マルチプログレスバーを生成
     let m = MultiProgress::new();
 
     // タスクのベクタに、initステップの数だけクロージャを格納してテスト実行の前準備
-    let tasks: Vec<JoinHandle<Result<(String, Response), Error>>> = init_steps
+    let tasks: Vec<JoinHandle<AppResult<(String, Response)>>> = init_steps
         .iter()
         .enumerate()
         .map(|(index, init_step)| {
@@ -143,7 +742,12 @@ pub async fn run_init(
             pb.enable_steady_tick(std::time::Duration::from_millis(50));
 
             // アクセスするURLを作成する
-            let url = format!("{}{}", base_url, init_step.path);
+            // パスの {key} は変数とデータのクエリの値で置換する
+            let url = format!(
+                "{}{}",
+                init_base_urls[index],
+                interpolate(&init_step.path, &init_variables[index])
+            );
             // ステータスのメッセージを変更
             pb.set_message(format!("Setting URL... -> [{name}]", name = init_name));
 
@@ -153,35 +757,20 @@ pub async fn run_init(
                 &url,
             );
 
-            // オプションのリクエストボディフラグがtrue, かつ、対応するデータが存在してリクエストボディが存在する場合は、jsonデータよりリクエストボディを設定する
-            if init_step.option.body
-                && !input_data_map.get(&init_step.ref_data).unwrap().is_empty()
-                && input_data_map
-                    .get(&init_step.ref_data)
-                    .unwrap()
-                    .get(0)
-                    .unwrap()
-                    .body
-                    .is_some()
-            {
-                // リクエストボディをjsonデータから取得
-                let init_data = input_data_map
-                    .get(&init_step.ref_data)
-                    .unwrap()
-                    .get(0)
-                    .unwrap()
-                    .body
-                    .clone()
-                    .unwrap_or_default();
+            // リクエストボディが存在する場合は、事前に生成したリクエストボディを設定する
+            if let Some(init_body) = &init_bodies[index] {
                 // ステータスのメッセージを変更
                 pb.set_message(format!(
                     "Setting the request body... -> [{name}]",
                     name = init_name
                 ));
                 // リクエストボディを設定
-                request = request.json(&init_data);
+                request = init_body.apply(request);
             }
 
+            // ヘッダを設定
+            request = request.headers(init_headers[index].clone());
+
             // ステータスのメッセージを変更
             pb.set_message(format!(
                 "Sending the request... -> [{name}]",
@@ -191,7 +780,7 @@ pub async fn run_init(
             // リクエストを送信
             tokio::spawn(async move {
                 // リクエストを送信
-                match request.send().await {
+                match client_clone.send(request).await {
                     Ok(response) => {
                         // ステータスのメッセージを変更
                         pb.finish_with_message(format!(
@@ -217,7 +806,7 @@ pub async fn run_init(
     let tasks_result = join_all(tasks).await;
 
     // タスクのベクタに格納したクロージャを実行
-    for (_, task) in tasks_result.into_iter().enumerate() {
+    for task in tasks_result {
         let (init_name, response) = task??;
 
         // クッキーをハッシュマップに格納する
@@ -235,7 +824,7 @@ pub async fn run_init(
             println!("[#] Response body: {} -> [{name}]", body, name = init_name);
         }
         println!("[*] Init step completed. -> [{name}]", name = init_name);
-        println!("")
+        println!()
     }
 
     Ok(cookie_map)
@@ -243,41 +832,39 @@ pub async fn run_init(
 
 // テストステップを実行する関数
 // 引数
-// - base_url: &String -> テスト対象のベースURL。不変参照
-// - steps: Vec<TestStep> -> テストステップの構造体の配列。所有権を移動する
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
 // - json_data: &JsonMap -> jsonデータの連想配列。不変参照
 // - cookie_map: &HashMap<String, String> -> クッキーの連想配列。不変参照
-// 戻り値：RaxResult<Vec<TestResult>> -> テスト結果の構造体のベクタをRaxResultでラップしたもの
+// - client: &HttpClient -> initステップと共有するHTTPクライアント。不変参照
+// - options: &RunOptions -> テスト実行のオプション。不変参照
+// 戻り値：RaxResult<(Vec<TestResult>, Option<OutputCoverage>)> -> テスト結果の構造体のベクタと、OpenAPIドキュメントが指定された場合はカバレッジをRaxResultでラップしたもの
 pub async fn run_test(
-    base_url: &String,
-    categories: HashMap<String, InputCaterogy>,
+    test_config: &InputConfigration,
     input_data_map: &InputDataMap,
     cookie_map: &HashMap<String, String>,
-    print_flag: &bool,
-    cookie_error_flag: &bool,
-) -> AppResult<Vec<OutputResult>> {
+    client: &HttpClient,
+    options: &RunOptions,
+) -> AppResult<(Vec<OutputResult>, Option<OutputCoverage>)> {
     // 結果を格納するベクタを初期化
     let mut results: Vec<OutputResult> = Vec::new();
+    // OpenAPIドキュメントが指定された場合は、カバレッジを集計する
+    let mut coverage = test_config.contract.as_ref().map(CoverageTracker::new);
 
-    println!("[*] Initializing HTTP client...");
-    // HTTPクライアントを初期化
-    let client = Client::new();
-
-    for (category_name, category) in categories.iter() {
+    for (category_name, category) in sorted_categories(test_config) {
         // マルチプログレスバーを生成
         let m = MultiProgress::new();
 
-        let flatten_step = gen_flatten_step(&category.steps, input_data_map)?;
+        let flatten_step = gen_flatten_step(test_config, category, input_data_map)?;
 
         // loginカテゴリが存在し、更にクッキーが存在しない場合の分岐
-        if category.login.is_some() {
-            if cookie_map.get(category.login.as_ref().unwrap()).is_none() {
+        if let Some(login) = &category.login {
+            if !cookie_map.contains_key(login) {
                 println!(
                     "[!] Cookie for login category is not found. -> [{name}]",
                     name = category_name
                 );
                 // クッキーが存在しない時に、エラーを返すかどうかの分岐
-                if !*cookie_error_flag {
+                if !options.cookie_error_continue {
                     return Err(anyhow!("Cookie Not found"));
                 } else {
                     continue;
@@ -286,7 +873,7 @@ pub async fn run_test(
         }
 
         // タスクのベクタに、テストステップの数だけクロージャを格納してテスト実行の前準備
-        let tasks: Vec<JoinHandle<Result<(String, u16, Response, Duration), Error>>> = flatten_step
+        let tasks: Vec<TestTask> = flatten_step
             .iter()
             .enumerate()
             .map(|(index, (_, _, test_step))| {
@@ -311,41 +898,75 @@ pub async fn run_test(
                 // ステータスのメッセージを変更
                 pb.set_message(format!("Preparing the request... -> [{}]", test_step_name));
 
-                // クエリが存在している場合はクエリを取得してパスを書き換える
-                let rewrite_path = if let Some(query) = &test_step.input_data.query {
-                    // ステータスのメッセージを変更
+                // クエリが存在している場合はステータスのメッセージを変更
+                if test_step.input_data.query.is_some() {
                     pb.set_message(format!(
                         "Setting the query... - [{name}]",
                         name = test_step_name
                     ));
+                }
 
-                    let test_query = query;
-
-                    // 正規表現をコンパイル
-                    let re = Regex::new(r"\{(\w+)\}").unwrap();
-                    // 正規表現にマッチした部分を、jsonデータから取得した値に置換する
-                    let replaced_string =
-                        re.replace_all(test_step.path.as_str(), |captures: &regex::Captures| {
-                            let key = &captures[1];
-                            let query_original = test_query.get(key).unwrap();
-                            match query_original {
-                                Value::String(s) => s.clone(),
-                                Value::Number(n) => n.to_string(),
-                                _ => "".to_string(),
-                            }
-                        });
-
-                    replaced_string.to_string()
-
-                // クエリの指定がない場合は、パスをそのまま使用する
-                } else {
-                    test_step.path.clone()
-                };
+                // プレースホルダにマッチした部分を、変数とjsonデータのクエリから取得した値に置換する
+                let rewrite_path = interpolate(&test_step.path, &test_step.variables);
 
                 // ステータスのメッセージを変更
                 pb.set_message(format!("Setting URL... - [{name}]", name = test_step_name));
                 // アクセスするURLを作成する
-                let url = format!("{}{}", base_url, rewrite_path);
+                let url = format!("{}{}", test_step.base_url, rewrite_path);
+
+                // WebSocketのステップの場合は、接続してシナリオのメッセージを送受信する
+                // ハンドシェイクにはHTTPのステップと同じく、ログインのクッキーとステップのヘッダを設定する
+                if let Some(scenario) = test_step.websocket.clone() {
+                    // カセットはHTTPのリクエストのみを記録するため、リプレイでは接続しない
+                    if client.is_replay() {
+                        pb.finish_with_message(format!(
+                            "WebSocket steps are not replayed. -> [{name}]",
+                            name = test_step_name
+                        ));
+                        return tokio::spawn(async {
+                            Err(anyhow!(
+                                "websocket steps cannot be replayed from a cassette"
+                            ))
+                        });
+                    }
+
+                    let mut headers = HeaderMap::new();
+                    if let Some(cookie) = category
+                        .login
+                        .as_ref()
+                        .and_then(|login| cookie_map.get(login))
+                        .and_then(|cookie| HeaderValue::from_str(cookie).ok())
+                    {
+                        headers.insert(reqwest::header::COOKIE, cookie);
+                    }
+                    headers.extend(test_step.headers.clone());
+
+                    // ステータスバーの表示を変更
+                    pb.set_message(format!(
+                        "Connecting the WebSocket... -> [{name}]",
+                        name = test_step_name
+                    ));
+
+                    return tokio::spawn(async move {
+                        let start_time = Instant::now();
+                        let result = run_websocket(&url, headers, &scenario).await;
+                        let elapsed_time = start_time.elapsed();
+
+                        // ステータスバーの表示を変更
+                        pb.finish_with_message(format!(
+                            "{} -> [{name}]",
+                            if result.is_ok() {
+                                "WebSocket scenario completed."
+                            } else {
+                                "WebSocket connection failed."
+                            },
+                            name = test_step_name
+                        ));
+                        result.map(|(response, failures)| {
+                            (test_step_name, response, elapsed_time, failures)
+                        })
+                    });
+                }
 
                 // リクエストクライアントの作成
                 let mut request = client_clone.request(
@@ -354,16 +975,16 @@ pub async fn run_test(
                     &url,
                 );
 
-                // オプションのリクエストボディフラグがtrue, かつ対応するデータが存在してリクエストボディが存在する場合は、jsonデータよりリクエストボディを設定する
-                if let Some(body) = &test_step.input_data.body {
+                // オプションのリクエストボディフラグがtrue, かつ対応するデータが存在してリクエストボディが存在する場合は、生成したリクエストボディを設定する
+                if let Some(body) = &test_step.request_body {
                     // ステータスバーの表示を変更
                     pb.set_message(format!(
                         "Setting the request body... -> [{name}]",
                         name = test_step_name
                     ));
 
-                    // リクエストボディをjsonデータから取得
-                    request = request.json(body);
+                    // リクエストボディをボディの形式に従って設定
+                    request = body.apply(request);
                 }
 
                 // リクエストを送信
@@ -382,29 +1003,60 @@ pub async fn run_test(
                     request = request.header("Cookie", cookie);
                 }
 
+                // ヘッダを設定
+                // Cookieヘッダが指定されている場合は、ログインのクッキーを上書きする
+                request = request.headers(test_step.headers.clone());
+
                 // ステータスバーの表示を変更
                 pb.set_message(format!(
                     "Sending the request... -> [{name}]",
                     name = test_step_name
                 ));
 
-                // 予期するステータスコードを設定
-                let expect_status = test_step.input_data.expect_status;
-
+                let stream = test_step.stream.clone();
+                // ストリーミングのステップはカセットを使用しないため、リプレイでは送信しない
+                if stream.is_some() && client.is_replay() {
+                    pb.finish_with_message(format!(
+                        "Streaming steps are not replayed. -> [{name}]",
+                        name = test_step_name
+                    ));
+                    return tokio::spawn(async {
+                        Err(anyhow!("stream steps cannot be replayed from a cassette"))
+                    });
+                }
                 tokio::spawn(async move {
                     let start_time = Instant::now();
 
-                    match request.send().await {
+                    // ストリーミングのステップは、レスポンスを全て読み込めないためカセットを使用しない
+                    let sent = match &stream {
+                        Some(_) => request.send().await.map_err(anyhow::Error::from),
+                        None => client_clone.send(request).await,
+                    };
+
+                    match sent {
                         // インデックスとレスポンスをタプルにして返す
                         Ok(response) => {
+                            // 所要時間はレスポンスヘッダを受信するまでの時間とする
                             let elapsed_time = start_time.elapsed();
 
+                            // ストリーミングのステップの場合は、範囲内のイベントを読み込んで検証する
+                            let (response, failures) = match &stream {
+                                Some(stream) => {
+                                    pb.set_message(format!(
+                                        "Reading the stream... -> [{name}]",
+                                        name = test_step_name
+                                    ));
+                                    stream.read(response).await
+                                }
+                                None => (response, Vec::new()),
+                            };
+
                             // ステータスバーの表示を変更
                             pb.finish_with_message(format!(
                                 "Request succeeded. -> [{name}]",
                                 name = test_step_name
                             ));
-                            return Ok((test_step_name, expect_status, response, elapsed_time));
+                            Ok((test_step_name, response, elapsed_time, failures))
                         }
                         Err(e) => {
                             // ステータスバーの表示を変更
@@ -412,9 +1064,9 @@ pub async fn run_test(
                                 "Request failed. -> [{name}]",
                                 name = test_step_name
                             ));
-                            return Err(e.into());
+                            Err(e)
                         }
-                    };
+                    }
                 })
             })
             .collect();
@@ -422,23 +1074,59 @@ pub async fn run_test(
         let tasks_result = join_all(tasks).await;
 
         // タスクのベクタをイテレートして、レスポンスを受け取る
-        for task in tasks_result {
+        // タスクはフラットされたステップと同じ順序で並んでいる
+        for ((_, _, test_step), task) in flatten_step.iter().zip(tasks_result) {
             // タスクの結果を受け取る
-            let (test_step_name, expect_status, response, elapsed_time) = task??;
-            // ステータスコードを取得
+            // WebSocketとストリーミングのステップは、接続できなかった場合もテストを中断せず、失敗として扱う
+            let (test_step_name, response, elapsed_time, received_failures) = match task? {
+                Ok(received) => received,
+                Err(e) if test_step.websocket.is_some() || test_step.stream.is_some() => {
+                    let test_step_name = format!("{}/{}", category_name, test_step.name);
+                    println!(
+                        "[!] Test failed! ({}) -> [{name}]",
+                        e,
+                        name = test_step_name
+                    );
+                    results.push(OutputResult {
+                        name: test_step_name,
+                        category: category_name.clone(),
+                        status: "failure".to_string(),
+                        message: format!("failed ({})", e),
+                        duration: 0.0,
+                        contract_violations: Vec::new(),
+                    });
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            // 予期するステータスコードを取得
+            let expect_status = &test_step.input_data.expect_status;
+            // ステータスコードとヘッダを取得
             let status = response.status();
+            let headers = response.headers().clone();
+            // レスポンスボディはスキーマと契約の検証、詳細表示で使用する
+            let body = if options.print_flag
+                || test_step.expect_schema.is_some()
+                || test_step.expect_body.is_some()
+                || test_step.graphql.is_some()
+                || test_config.contract.is_some()
+            {
+                response.text().await?
+            } else {
+                String::new()
+            };
 
             // 詳細表示フラグがtrueの場合は詳細を表示する
-            if *print_flag {
+            if options.print_flag {
                 println!("[*] Status: {} -> [{name}]", status, name = test_step_name);
                 println!(
                     "[*] Headers: {:?} -> [{name}]",
-                    response.headers(),
+                    headers,
                     name = test_step_name
                 );
                 println!(
                     "[*] Response body: {} -> [{name}]",
-                    response.text().await?,
+                    body,
                     name = test_step_name
                 );
                 println!(
@@ -448,43 +1136,160 @@ pub async fn run_test(
                 );
             }
 
-            // ステータスコードが期待値と一致するか確認し、結果を格納
-            if status.as_u16() == expect_status {
-                println!("[#] Test passed! -> [{name}]", name = test_step_name);
-                results.push(OutputResult {
-                    name: test_step_name.clone(),
-                    category: category_name.clone(),
-                    status: "success".to_string(),
-                    message: format!(
-                        "success (status: {}, expect status: {})",
+            // 結果のメッセージに含める詳細と、テストが成功したかどうか
+            let mut details: Vec<String> = Vec::new();
+            let mut passed = true;
+
+            // ステータスコードが期待値と一致するか確認する
+            match expect_status.matches(status.as_u16()) {
+                // 複数の候補やクラスで指定された場合は、一致したパターンをメッセージに含める
+                Some(pattern) if !expect_status.is_single_code() => details.push(format!(
+                    "status: {}, expect status: {}, matched: {}",
+                    status, expect_status, pattern
+                )),
+                Some(_) => details.push(format!(
+                    "status: {}, expect status: {}",
+                    status, expect_status
+                )),
+                None => {
+                    passed = false;
+                    details.push(format!(
+                        "status: {}, expect status: {}",
                         status, expect_status
-                    ),
-                    duration: elapsed_time.as_secs_f64(),
-                });
-            // 一致しない場合は、失敗として結果を格納
-            } else {
+                    ));
+                }
+            }
+
+            // WebSocketとストリーミングのステップの場合は、受信したメッセージとイベントが予期した通りか確認する
+            if !received_failures.is_empty() {
+                passed = false;
+                details.extend(received_failures);
+            }
+
+            // レスポンスボディがスキーマに適合するか確認する
+            if let Some(schema) = test_step.expect_schema.as_ref() {
+                if let Some(detail) = validate_body(schema, &body) {
+                    passed = false;
+                    details.push(detail);
+                }
+            }
+
+            // レスポンスボディがテキストとXPathの検証に適合するか確認する
+            if let Some(assertion) = test_step.expect_body.as_ref() {
+                let failures = assertion.check(&body);
+                if !failures.is_empty() {
+                    passed = false;
+                    details.extend(failures);
+                }
+            }
+
+            // GraphQLのレスポンスのerrorsとdataが予期した通りか確認する
+            if let Some(assertion) = test_step.graphql.as_ref() {
+                let failures = assertion.check(&body);
+                if !failures.is_empty() {
+                    passed = false;
+                    details.extend(failures);
+                }
+            }
+
+            // 所要時間が上限を超えていないか確認する
+            if let Some(max_duration) = test_step.max_duration {
+                if elapsed_time > max_duration {
+                    let detail = format!(
+                        "duration: {}ms, max duration: {}ms",
+                        elapsed_time.as_millis(),
+                        max_duration.as_millis()
+                    );
+                    match test_config.max_duration_mode {
+                        MaxDurationMode::Fail => {
+                            passed = false;
+                            details.push(detail);
+                        }
+                        MaxDurationMode::Warn => {
+                            println!(
+                                "[!] Max duration exceeded! ({}) -> [{name}]",
+                                detail,
+                                name = test_step_name
+                            );
+                            details.push(format!("warning: {}", detail));
+                        }
+                    }
+                }
+            }
+
+            // リクエストとレスポンスがOpenAPIドキュメントに適合するか確認する
+            // 契約違反はテストの成否とは別に報告する
+            // パスはベースURLのパスを含めて照合し、ドキュメントのサーバーのパスは契約側で取り除く
+            let request_path = format!(
+                "{}{}",
+                base_path(&test_step.base_url),
+                interpolate(&test_step.path, &test_step.variables)
+            );
+            // WebSocketのハンドシェイク(101)はドキュメントに記述されないことが多く、ボディは受信したメッセージのため、
+            // ステータスコードとレスポンスボディは検証しない
+            let handshake =
+                test_step.websocket.is_some() && status == StatusCode::SWITCHING_PROTOCOLS;
+            let contract_violations = match test_config.contract.as_ref() {
+                Some(contract) => contract.check(
+                    &test_step.method,
+                    &request_path,
+                    test_step.request_body.as_ref(),
+                    (!handshake).then_some(status.as_u16()),
+                    &body,
+                ),
+                None => Vec::new(),
+            };
+            if let Some(coverage) = coverage.as_mut() {
+                coverage.record(&test_step.method, &request_path, status.as_u16());
+            }
+            if !contract_violations.is_empty() {
                 println!(
-                    "[!] Test failed! (status: {}, expect status: {}) -> [{name}]",
-                    status,
-                    expect_status,
+                    "[!] Contract violation! ({}) -> [{name}]",
+                    contract_violations.join(", "),
                     name = test_step_name
                 );
+            }
 
-                results.push(OutputResult {
-                    name: test_step_name.clone(),
-                    category: category_name.clone(),
-                    status: "failure".to_string(),
-                    message: format!(
-                        "failed (status: {}, expect status: {})",
-                        status, expect_status
-                    ),
-                    duration: elapsed_time.as_secs_f64(),
-                });
+            // 結果を格納
+            if passed {
+                println!("[#] Test passed! -> [{name}]", name = test_step_name);
+            } else {
+                println!(
+                    "[!] Test failed! ({}) -> [{name}]",
+                    details.join(", "),
+                    name = test_step_name
+                );
+                // 失敗したリクエストを再現するcurlコマンドを表示する
+                // WebSocketのステップはcurlコマンドで再現できないため表示しない
+                if options.curl_on_failure && test_step.websocket.is_none() {
+                    let cookie = category
+                        .login
+                        .as_ref()
+                        .and_then(|login| cookie_map.get(login));
+                    println!(
+                        "[*] Reproduce with curl: -> [{name}]\n{}",
+                        to_curl(test_step, cookie.map(String::as_str), options.redact),
+                        name = test_step_name
+                    );
+                }
             }
+
+            results.push(OutputResult {
+                name: test_step_name.clone(),
+                category: category_name.clone(),
+                status: if passed { "success" } else { "failure" }.to_string(),
+                message: format!(
+                    "{} ({})",
+                    if passed { "success" } else { "failed" },
+                    details.join(", ")
+                ),
+                duration: elapsed_time.as_secs_f64(),
+                contract_violations,
+            });
         }
     }
 
-    Ok(results)
+    Ok((results, coverage.map(|coverage| coverage.summary())))
 }
 
 // テストの結果を出力する関数
@@ -492,17 +1297,39 @@ pub async fn run_test(
 // - base_url: テスト対象のURL。不変参照
 // - output_json_path: 出力するJSONファイルのパス。不変参照
 // - results: テストの結果。所有権を移動
+// - coverage: OpenAPIドキュメントのカバレッジ。所有権を移動
 // 戻り値
 // - RaxResult<()>: RaxResult型
 pub fn render_results(
-    base_url: &String,
-    output_json_path: &String,
+    base_url: &str,
+    output_json_path: &str,
     results: Vec<OutputResult>,
+    coverage: Option<OutputCoverage>,
 ) -> AppResult<()> {
+    // カバレッジの概要を表示する
+    if let Some(coverage) = coverage.as_ref() {
+        println!(
+            "[*] Coverage: operations {}/{} ({:.1}%), responses {}/{} ({:.1}%)",
+            coverage.tested_operations,
+            coverage.operations,
+            coverage.operation_percent,
+            coverage.tested_responses,
+            coverage.responses,
+            coverage.response_percent
+        );
+        for operation in coverage.untested_operations.iter() {
+            println!("[!] Untested operation -> [{}]", operation);
+        }
+        for response in coverage.untested_responses.iter() {
+            println!("[!] Untested response -> [{}]", response);
+        }
+    }
+
     // 書き出すJSONデータを作成する
     let result_data = OutputData {
-        base_url: base_url.clone(),
-        results: results,
+        base_url: base_url.to_string(),
+        results,
+        coverage,
     };
 
     println!("[*] Outputting test results...");
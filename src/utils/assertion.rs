@@ -0,0 +1,118 @@
+use anyhow::anyhow;
+use regex::Regex;
+use sxd_document::parser;
+use sxd_xpath::{Context, Factory, Value as XPathValue};
+
+use super::types::{AppResult, ExpectBody, ExpectXPath};
+
+// 正規表現を検証済みのレスポンスボディの検証を格納する構造体
+#[derive(Debug, Clone)]
+pub struct BodyAssertion {
+    contains: Vec<String>,
+    not_contains: Vec<String>,
+    regex: Vec<Regex>,
+    xpath: Vec<ExpectXPath>,
+}
+
+// レスポンスボディの検証の正規表現とXPathを検証する関数
+// 引数：expect_body: &ExpectBody -> レスポンスボディの検証。不変参照
+// 戻り値：AppResult<BodyAssertion> -> 検証済みのレスポンスボディの検証をAppResultでラップしたもの
+pub fn compile_body_assertion(expect_body: &ExpectBody) -> AppResult<BodyAssertion> {
+    let regex = expect_body
+        .regex
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(|e| anyhow!("Invalid regex: {}", e)))
+        .collect::<AppResult<Vec<Regex>>>()?;
+
+    // XPathは評価時に再度構築するため、ここでは構文のみを検証する
+    let factory = Factory::new();
+    for xpath in expect_body.xpath.iter() {
+        factory
+            .build(&xpath.path)
+            .map_err(|e| anyhow!("Invalid xpath: {} ({})", xpath.path, e))?
+            .ok_or_else(|| anyhow!("Invalid xpath: {}", xpath.path))?;
+    }
+
+    Ok(BodyAssertion {
+        contains: expect_body.contains.clone(),
+        not_contains: expect_body.not_contains.clone(),
+        regex,
+        xpath: expect_body.xpath.clone(),
+    })
+}
+
+impl BodyAssertion {
+    // レスポンスボディを検証する関数
+    // 引数：body: &str -> レスポンスボディ
+    // 戻り値：Vec<String> -> 検証に失敗した内容の配列。全て成功した場合は空
+    pub fn check(&self, body: &str) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        for text in self.contains.iter().filter(|text| !body.contains(*text)) {
+            failures.push(format!("body does not contain: {}", text));
+        }
+        for text in self.not_contains.iter().filter(|text| body.contains(*text)) {
+            failures.push(format!("body contains: {}", text));
+        }
+        for regex in self.regex.iter().filter(|regex| !regex.is_match(body)) {
+            failures.push(format!("body does not match: {}", regex));
+        }
+
+        if !self.xpath.is_empty() {
+            failures.extend(check_xpath(&self.xpath, body));
+        }
+
+        failures
+    }
+}
+
+// XMLのボディをXPathで検証する関数
+// ノードの集合は文書順で最初のノードの文字列値を、それ以外は評価結果の文字列を比較する
+// 引数
+// - xpath: &[ExpectXPath] -> XPathの検証の配列
+// - body: &str -> レスポンスボディ
+// 戻り値：Vec<String> -> 検証に失敗した内容の配列。全て成功した場合は空
+fn check_xpath(xpath: &[ExpectXPath], body: &str) -> Vec<String> {
+    let package = match parser::parse(body) {
+        Ok(package) => package,
+        Err(e) => return vec![format!("response body is not xml (at {})", e.location())],
+    };
+    let document = package.as_document();
+    let factory = Factory::new();
+    let context = Context::new();
+
+    let mut failures = Vec::new();
+    for expect in xpath {
+        // 構文は生成時に検証済み
+        let value = match factory.build(&expect.path) {
+            Ok(Some(compiled)) => compiled.evaluate(&context, document.root()),
+            _ => continue,
+        };
+        let value = match value {
+            Ok(value) => value,
+            Err(e) => {
+                failures.push(format!("xpath {}: {}", expect.path, e));
+                continue;
+            }
+        };
+
+        match (&value, &expect.equals) {
+            (XPathValue::Nodeset(nodes), _) if nodes.size() == 0 => {
+                failures.push(format!("xpath not found: {}", expect.path));
+            }
+            (XPathValue::Boolean(false), None) => {
+                failures.push(format!("xpath is false: {}", expect.path));
+            }
+            (value, Some(equals)) if value.string() != *equals => {
+                failures.push(format!(
+                    "xpath {}: {}, expect: {}",
+                    expect.path,
+                    value.string(),
+                    equals
+                ));
+            }
+            _ => {}
+        }
+    }
+    failures
+}
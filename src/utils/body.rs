@@ -0,0 +1,228 @@
+use anyhow::{anyhow, Context};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::multipart::{Form, Part};
+use reqwest::RequestBuilder;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::types::{AppResult, BodyType, InputData};
+use super::{sorted_object, value_to_string};
+
+// 送信するリクエストボディを定義する
+// ファイルはフラットされたステップの生成時に読み込み、送信時には失敗しないようにする
+#[derive(Debug, Clone)]
+pub enum RequestBody {
+    Json(Value),
+    // キーの順にソートしたフォームの値
+    Form(Vec<(String, String)>),
+    // キーの順にソートしたマルチパートのパート
+    Multipart(Vec<MultipartPart>),
+    Raw(String),
+    Binary { path: PathBuf, content: Vec<u8> },
+}
+
+// マルチパートのパートを格納する構造体
+#[derive(Debug, Clone)]
+pub struct MultipartPart {
+    pub name: String,
+    pub value: PartValue,
+    // ファイル名。ファイルの場合はファイルのパスのファイル名を既定値とする
+    pub filename: Option<String>,
+    // Content-Type。ファイルの場合は拡張子から推測した値を既定値とする
+    pub content_type: Option<String>,
+}
+
+// マルチパートのパートの値を定義する
+#[derive(Debug, Clone)]
+pub enum PartValue {
+    Text(String),
+    File { path: PathBuf, content: Vec<u8> },
+}
+
+// データからリクエストボディを生成する関数
+// 引数
+// - body_type: BodyType -> リクエストボディの形式
+// - data: &InputData -> データ。不変参照
+// - base_dir: &Path -> ファイルのパスの基準となるディレクトリ。データを宣言したファイルのディレクトリがある場合はそちらを使用する。不変参照
+// 戻り値：AppResult<Option<RequestBody>> -> リクエストボディをAppResultでラップしたもの。送信するボディがない場合はNone
+pub fn gen_request_body(
+    body_type: BodyType,
+    data: &InputData,
+    base_dir: &Path,
+) -> AppResult<Option<RequestBody>> {
+    let base_dir = data.base_dir.as_deref().unwrap_or(base_dir);
+    // マップの値はキーの順に並べ、送信するボディを実行ごとに同じにする
+    let sorted_body = data
+        .body
+        .as_ref()
+        .map(|body| body.iter().collect::<BTreeMap<&String, &Value>>());
+
+    let request_body = match body_type {
+        BodyType::Json => data
+            .body
+            .as_ref()
+            .map(|body| RequestBody::Json(sorted_object(body))),
+        BodyType::Form => sorted_body.map(|body| {
+            RequestBody::Form(
+                body.into_iter()
+                    .map(|(key, value)| (key.clone(), value_to_string(value)))
+                    .collect(),
+            )
+        }),
+        BodyType::Multipart => match sorted_body {
+            Some(body) => Some(RequestBody::Multipart(
+                body.into_iter()
+                    .map(|(name, value)| gen_part(name, value, base_dir))
+                    .collect::<AppResult<Vec<MultipartPart>>>()?,
+            )),
+            None => None,
+        },
+        BodyType::Raw => match (&data.raw_body, &data.body_file) {
+            (Some(raw_body), _) => Some(RequestBody::Raw(raw_body.clone())),
+            // ファイルから読み込む場合は、テキストとして送信する
+            (None, Some(body_file)) => {
+                let path = base_dir.join(body_file);
+                let content = String::from_utf8(read_file(&path)?)
+                    .with_context(|| format!("Body file is not text: {}", path.display()))?;
+                Some(RequestBody::Raw(content))
+            }
+            (None, None) => {
+                return Err(anyhow!(
+                    "raw_body or body_file is required for body_type raw"
+                ))
+            }
+        },
+        BodyType::Binary => {
+            let path = base_dir.join(
+                data.body_file
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("body_file is required for body_type binary"))?,
+            );
+            let content = read_file(&path)?;
+            Some(RequestBody::Binary { path, content })
+        }
+    };
+
+    Ok(request_body)
+}
+
+// マルチパートのパートを生成する関数
+// 値は次の形式で指定する
+// - 文字列、数値など -> テキストのパート
+// - { file: パス, filename: ファイル名, content_type: Content-Type } -> ファイルのパート
+// - { value: 値, content_type: Content-Type } -> Content-Typeを指定したテキストのパート
+// - その他のオブジェクトと配列 -> jsonの文字列のテキストのパート
+// 引数
+// - name: &str -> パートの名前
+// - value: &Value -> パートの値。不変参照
+// - base_dir: &Path -> ファイルのパスの基準となるディレクトリ。不変参照
+// 戻り値：AppResult<MultipartPart> -> パートをAppResultでラップしたもの
+fn gen_part(name: &str, value: &Value, base_dir: &Path) -> AppResult<MultipartPart> {
+    let option = |key: &str| value.get(key).map(value_to_string);
+    let content_type = option("content_type");
+    if let Some(content_type) = &content_type {
+        HeaderValue::from_str(content_type)
+            .map_err(|_| anyhow!("Invalid content_type of multipart part: {}", name))?;
+    }
+
+    let part = match value.get("file").and_then(Value::as_str) {
+        Some(file) => {
+            let path = base_dir.join(file);
+            let content = read_file(&path)?;
+            MultipartPart {
+                name: name.to_string(),
+                filename: option("filename").or_else(|| {
+                    path.file_name()
+                        .map(|filename| filename.to_string_lossy().to_string())
+                }),
+                content_type: content_type.or_else(|| {
+                    mime_guess::from_path(&path)
+                        .first()
+                        .map(|mime| mime.to_string())
+                }),
+                value: PartValue::File { path, content },
+            }
+        }
+        None => MultipartPart {
+            name: name.to_string(),
+            value: PartValue::Text(match value.get("value") {
+                Some(value) => value_to_string(value),
+                None => value_to_string(value),
+            }),
+            filename: option("filename").filter(|_| value.get("value").is_some()),
+            content_type: content_type.filter(|_| value.get("value").is_some()),
+        },
+    };
+
+    Ok(part)
+}
+
+// ファイルを読み込む関数
+// 引数：path: &Path -> ファイルのパス。不変参照
+// 戻り値：AppResult<Vec<u8>> -> ファイルの内容をAppResultでラップしたもの
+fn read_file(path: &Path) -> AppResult<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("Failed to read body file: {}", path.display()))
+}
+
+impl RequestBody {
+    // リクエストにボディを設定する関数
+    // Content-Typeはボディの形式から設定し、ヘッダで指定された場合は後から上書きされる
+    // 引数：request: RequestBuilder -> リクエストビルダー。所有権を移動する
+    // 戻り値：RequestBuilder -> ボディを設定したリクエストビルダー
+    pub fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            RequestBody::Json(body) => request.json(body),
+            RequestBody::Form(pairs) => request.form(pairs),
+            RequestBody::Multipart(parts) => {
+                let form = parts.iter().fold(Form::new(), |form, part| {
+                    let mut multipart_part = match &part.value {
+                        PartValue::Text(text) => Part::text(text.clone()),
+                        PartValue::File { content, .. } => Part::bytes(content.clone()),
+                    };
+                    if let Some(filename) = &part.filename {
+                        multipart_part = multipart_part.file_name(filename.clone());
+                    }
+                    // Content-Typeは生成時に検証済み
+                    if let Some(content_type) = part
+                        .content_type
+                        .as_ref()
+                        .and_then(|content_type| HeaderValue::from_str(content_type).ok())
+                    {
+                        let mut headers = HeaderMap::new();
+                        headers.insert(CONTENT_TYPE, content_type);
+                        multipart_part = multipart_part.headers(headers);
+                    }
+                    form.part(part.name.clone(), multipart_part)
+                });
+                request.multipart(form)
+            }
+            RequestBody::Raw(body) => request
+                .header(CONTENT_TYPE, "text/plain")
+                .body(body.clone()),
+            RequestBody::Binary { path, content } => request
+                .header(CONTENT_TYPE, binary_content_type(path))
+                .body(content.clone()),
+        }
+    }
+
+    // jsonのボディの場合は、その値を返す関数
+    // 契約の検証など、jsonのボディのみを扱う処理で使用する
+    // 戻り値：Option<&Value> -> jsonのボディ。json以外の場合はNone
+    pub fn as_json(&self) -> Option<&Value> {
+        match self {
+            RequestBody::Json(body) => Some(body),
+            _ => None,
+        }
+    }
+}
+
+// binaryのボディのContent-Typeを返す関数
+// 拡張子から推測できない場合はapplication/octet-streamとする
+// 引数：path: &Path -> ファイルのパス。不変参照
+// 戻り値：String -> Content-Type
+pub fn binary_content_type(path: &Path) -> String {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .to_string()
+}
@@ -0,0 +1,290 @@
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, Request, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{to_writer_pretty, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::sync::{Arc, Mutex};
+
+use super::types::AppResult;
+
+// カセットファイルの構造体を定義する
+// 記録したリクエストとレスポンスの組を、記録した順に格納する
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Cassette {
+    // データ生成に使用したシード。リプレイ時も同じシードで生成し、同じリクエストを送信する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    // 記録を開始した時刻(RFC 3339)。記録時とリプレイ時は、nowにこの時刻を使用する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recorded_at: Option<String>,
+    pub interactions: Vec<Interaction>,
+}
+
+// リクエストとレスポンスの組を格納する構造体
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+// 記録したリクエストを格納する構造体
+// リプレイ時はメソッド、URL、ボディが全て一致するものを探す
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    // jsonのボディは値として比較するため、パースして格納する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+// 記録したレスポンスを格納する構造体
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    // 同名のヘッダ(Set-Cookieなど)が複数存在する場合があるため、値は配列で格納する
+    #[serde(default)]
+    pub headers: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub body: String,
+}
+
+// リクエストの送信方法を定義する
+#[derive(Clone)]
+enum Mode {
+    // ネットワークに送信する
+    Network,
+    // ネットワークに送信し、リクエストとレスポンスを記録する
+    Record(Arc<Mutex<Vec<Interaction>>>),
+    // ネットワークに送信せず、記録したレスポンスを返す
+    // 同じリクエストが複数記録されている場合は、未使用のものから順に返す
+    Replay(Arc<Mutex<Vec<(Interaction, bool)>>>),
+}
+
+// initステップとテストステップで共有するHTTPクライアントの構造体
+// 記録・リプレイのモードに応じてリクエストを送信する
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    mode: Mode,
+    // データ生成に使用するシード。記録時とリプレイ時のみ設定する
+    seed: Option<u64>,
+    // データ生成のnowに使用する時刻。記録時とリプレイ時のみ設定する
+    now: Option<DateTime<Utc>>,
+}
+
+impl HttpClient {
+    // ネットワークに送信するクライアントを生成する関数
+    // 戻り値：HttpClient
+    pub fn network() -> Self {
+        HttpClient {
+            client: Client::new(),
+            mode: Mode::Network,
+            seed: None,
+            now: None,
+        }
+    }
+
+    // リクエストとレスポンスを記録するクライアントを生成する関数
+    // リプレイ時に同じリクエストを生成できるように、シードと記録を開始した時刻を決めておく
+    // 引数：seed: Option<u64> -> データ生成に使用する乱数のシード。指定がない場合はランダムに決める
+    // 戻り値：HttpClient
+    pub fn record(seed: Option<u64>) -> Self {
+        HttpClient {
+            client: Client::new(),
+            mode: Mode::Record(Arc::new(Mutex::new(Vec::new()))),
+            seed: Some(seed.unwrap_or_else(rand::random)),
+            now: Some(Utc::now()),
+        }
+    }
+
+    // カセットファイルを読み込み、記録したレスポンスを返すクライアントを生成する関数
+    // 引数：path: &str -> カセットファイルのパス
+    // 戻り値：AppResult<HttpClient> -> クライアントをAppResultでラップしたもの
+    pub fn replay(path: &str) -> AppResult<Self> {
+        println!("[*] Loading cassette file... -> [{}]", path);
+        let file =
+            File::open(path).with_context(|| format!("Failed to open cassette file: {}", path))?;
+        let cassette: Cassette = serde_json::from_reader(file)
+            .with_context(|| format!("Failed to parse cassette file: {}", path))?;
+        let now = cassette
+            .recorded_at
+            .as_deref()
+            .map(DateTime::parse_from_rfc3339)
+            .transpose()
+            .with_context(|| format!("Invalid recorded_at in cassette file: {}", path))?
+            .map(|now| now.with_timezone(&Utc));
+        let interactions = cassette
+            .interactions
+            .into_iter()
+            .map(|interaction| (interaction, false))
+            .collect();
+        Ok(HttpClient {
+            client: Client::new(),
+            mode: Mode::Replay(Arc::new(Mutex::new(interactions))),
+            seed: cassette.seed,
+            now,
+        })
+    }
+
+    // カセットファイルのレスポンスを返すモードかどうかを返す関数
+    // 戻り値：bool -> リプレイする場合はtrue
+    pub fn is_replay(&self) -> bool {
+        matches!(self.mode, Mode::Replay(_))
+    }
+
+    // データ生成に使用するシードを返す関数
+    // 戻り値：Option<u64> -> 記録時は決めたシード、リプレイ時はカセットファイルのシード
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    // データ生成のnowに使用する時刻を返す関数
+    // 戻り値：Option<DateTime<Utc>> -> 記録時とリプレイ時は記録を開始した時刻。それ以外はNone
+    pub fn now(&self) -> Option<DateTime<Utc>> {
+        self.now
+    }
+
+    // リクエストビルダーを生成する関数
+    // 引数
+    // - method: Method -> HTTPメソッド
+    // - url: &str -> リクエスト先のURL
+    // 戻り値：RequestBuilder -> リクエストビルダー
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    // モードに応じてリクエストを送信し、レスポンスを返す関数
+    // 引数：request: RequestBuilder -> 送信するリクエスト。所有権を移動する
+    // 戻り値：AppResult<Response> -> レスポンスをAppResultでラップしたもの
+    pub async fn send(&self, request: RequestBuilder) -> AppResult<Response> {
+        match &self.mode {
+            Mode::Network => Ok(request.send().await?),
+            Mode::Record(interactions) => {
+                let request = request.build()?;
+                let recorded_request = RecordedRequest::from_request(&request);
+                let response = self.client.execute(request).await?;
+
+                // ボディを記録するために読み込み、同じ内容のレスポンスを作り直す
+                let status = response.status();
+                let version = response.version();
+                let headers = response.headers().clone();
+                let body = response.bytes().await?;
+                interactions.lock().unwrap().push(Interaction {
+                    request: recorded_request,
+                    response: RecordedResponse::new(status.as_u16(), &headers, &body),
+                });
+
+                let mut rebuilt = http::Response::new(body.to_vec());
+                *rebuilt.status_mut() = status;
+                *rebuilt.version_mut() = version;
+                *rebuilt.headers_mut() = headers;
+                Ok(Response::from(rebuilt))
+            }
+            Mode::Replay(interactions) => {
+                let request = RecordedRequest::from_request(&request.build()?);
+                let mut interactions = interactions.lock().unwrap();
+                let index = interactions
+                    .iter()
+                    .position(|(interaction, used)| !used && interaction.request == request)
+                    .or_else(|| {
+                        interactions
+                            .iter()
+                            .position(|(interaction, _)| interaction.request == request)
+                    })
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "No recorded response in cassette: {} {}",
+                            request.method,
+                            request.url
+                        )
+                    })?;
+                interactions[index].1 = true;
+                interactions[index].0.response.to_response()
+            }
+        }
+    }
+
+    // 記録したリクエストとレスポンスをカセットファイルに書き出す関数
+    // 記録するモードでない場合は何もしない
+    // 引数：path: &str -> カセットファイルのパス
+    // 戻り値：AppResult<()>
+    pub fn save(&self, path: &str) -> AppResult<()> {
+        if let Mode::Record(interactions) = &self.mode {
+            println!("[*] Writing cassette file... -> [{}]", path);
+            let cassette = Cassette {
+                seed: self.seed,
+                recorded_at: self
+                    .now
+                    .map(|now| now.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+                interactions: interactions.lock().unwrap().clone(),
+            };
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create cassette file: {}", path))?;
+            to_writer_pretty(file, &cassette)?;
+        }
+        Ok(())
+    }
+}
+
+impl RecordedRequest {
+    // 送信するリクエストから、記録するリクエストを生成する関数
+    // jsonのボディはキーの順序に依存しないように値として格納する
+    // 引数：request: &Request -> 送信するリクエスト。不変参照
+    // 戻り値：RecordedRequest
+    fn from_request(request: &Request) -> Self {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|bytes| {
+                serde_json::from_slice(bytes)
+                    .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).to_string()))
+            });
+        RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body,
+        }
+    }
+}
+
+impl RecordedResponse {
+    // 受信したレスポンスから、記録するレスポンスを生成する関数
+    // 引数
+    // - status: u16 -> ステータスコード
+    // - headers: &HeaderMap -> レスポンスヘッダ。不変参照
+    // - body: &[u8] -> レスポンスボディ
+    // 戻り値：RecordedResponse
+    fn new(status: u16, headers: &HeaderMap, body: &[u8]) -> Self {
+        let mut recorded_headers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, value) in headers.iter() {
+            recorded_headers
+                .entry(name.to_string())
+                .or_default()
+                .push(String::from_utf8_lossy(value.as_bytes()).to_string());
+        }
+        RecordedResponse {
+            status,
+            headers: recorded_headers,
+            body: String::from_utf8_lossy(body).to_string(),
+        }
+    }
+
+    // 記録したレスポンスから、reqwestのレスポンスを生成する関数
+    // 戻り値：AppResult<Response> -> レスポンスをAppResultでラップしたもの
+    fn to_response(&self) -> AppResult<Response> {
+        let mut builder = http::Response::builder().status(self.status);
+        for (name, values) in self.headers.iter() {
+            for value in values {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+        }
+        let response = builder
+            .body(self.body.clone())
+            .map_err(|e| anyhow!("Invalid response in cassette: {}", e))?;
+        Ok(Response::from(response))
+    }
+}
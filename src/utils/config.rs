@@ -0,0 +1,378 @@
+use anyhow::{anyhow, Context};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use super::data::DATA_SCHEMES;
+use super::env::{expand_env_vars, expand_yaml_value};
+use super::types::AppResult;
+
+// テスト構成ファイルを読み込み、includeされたファイルをマージしたyamlの値を返す関数
+// 引数
+// - path: &Path -> テスト構成ファイルの絶対パス。不変参照
+// - stack: &mut Vec<PathBuf> -> 読み込み中のファイルのパスのスタック。循環したincludeの検出に使用する
+// - data_dirs: &mut HashMap<String, PathBuf> -> データファイルのパスと、そのパスが書かれたファイルのディレクトリの連想配列。可変参照
+// 戻り値：AppResult<Value> -> マージしたyamlの値をAppResultでラップしたもの
+pub fn load_config_value(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    data_dirs: &mut HashMap<String, PathBuf>,
+) -> AppResult<Value> {
+    if stack.iter().any(|loading| loading == path) {
+        return Err(anyhow!("Circular include: {}", path.display()));
+    }
+
+    let config_file = File::open(path)
+        .with_context(|| format!("Failed to open config file: {}", path.display()))?;
+    let mut config_value: Value = serde_yaml::from_reader(BufReader::new(config_file))
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    // アンカーのマージキー(<<)を展開する
+    config_value.apply_merge()?;
+
+    let mut config_map = match config_value {
+        Value::Mapping(config_map) => config_map,
+        _ => return Err(anyhow!("Invalid config file: {}", path.display())),
+    };
+
+    // ファイルのパスは読み込みに使用するため、他の値より先に環境変数を展開する
+    for key in ["include", "data"] {
+        if let Some(value) = config_map.get_mut(key) {
+            expand_yaml_value(value)?;
+        }
+    }
+
+    // データファイルなどのパスは、そのパスが書かれたファイルのディレクトリを基準に解決する
+    let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    // includeしたファイルのデータと連結できるように配列に揃える
+    if let Some(data) = config_map.get_mut("data") {
+        resolve_data_paths(data, &base_dir, data_dirs);
+        if data.is_string() {
+            *data = Value::Sequence(vec![data.clone()]);
+        }
+    }
+    resolve_file_paths(&mut config_map, &base_dir)?;
+
+    // includeされたファイルを順番にマージし、最後に自身の値をマージする
+    let includes = match config_map.remove("include") {
+        Some(include) => value_to_strings(include, "include")?,
+        None => Vec::new(),
+    };
+
+    stack.push(path.to_path_buf());
+    let mut merged_map = Mapping::new();
+    for include in includes {
+        let included = load_config_value(&base_dir.join(&include), stack, data_dirs)?;
+        if let Value::Mapping(included_map) = included {
+            merge_config(&mut merged_map, included_map)?;
+        }
+    }
+    stack.pop();
+
+    merge_config(&mut merged_map, config_map)?;
+
+    Ok(Value::Mapping(merged_map))
+}
+
+// テスト構成ファイルの文字列に含まれる環境変数を展開する関数
+// 選択されていない環境は使用しないため、環境変数を展開する前に取り除く
+// 引数
+// - config_value: &mut Value -> テスト構成ファイルのyamlの値。可変参照
+// - env_name: Option<&str> -> 適用する環境の名前
+// 戻り値：AppResult<()> -> 環境変数が存在せず、デフォルト値もない場合はエラー
+pub fn expand_config_env(config_value: &mut Value, env_name: Option<&str>) -> AppResult<()> {
+    if let Some(Value::Mapping(environments)) = config_value.get_mut("environments") {
+        environments.retain(|name, _| env_name.is_some() && name.as_str() == env_name);
+    }
+    expand_yaml_value(config_value)
+}
+
+// ステップのextendsに指定されたテンプレートを展開する関数
+// テンプレートの値をベースに、ステップに記述された値で上書きする
+// 引数：config_value: &mut Value -> テスト構成ファイルのyamlの値。可変参照
+// 戻り値：AppResult<()>
+pub fn resolve_templates(config_value: &mut Value) -> AppResult<()> {
+    let config_map = match config_value.as_mapping_mut() {
+        Some(config_map) => config_map,
+        None => return Ok(()),
+    };
+
+    let templates = match config_map.remove("templates") {
+        Some(Value::Mapping(templates)) => templates,
+        Some(_) => return Err(anyhow!("Invalid templates")),
+        None => Mapping::new(),
+    };
+
+    if let Some(Value::Sequence(steps)) = config_map.get_mut("init") {
+        for step in steps.iter_mut() {
+            extend_step(step, &templates, &mut Vec::new())?;
+        }
+    }
+
+    if let Some(Value::Mapping(categories)) = config_map.get_mut("categories") {
+        for (_, category) in categories.iter_mut() {
+            if let Some(Value::Sequence(steps)) = category.get_mut("steps") {
+                for step in steps.iter_mut() {
+                    extend_step(step, &templates, &mut Vec::new())?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ステップにテンプレートを適用する関数
+// テンプレートが更に別のテンプレートをextendsしている場合は再帰的に適用する
+// 引数
+// - step: &mut Value -> ステップのyamlの値。可変参照
+// - templates: &Mapping -> テンプレート名とテンプレートの連想配列。不変参照
+// - stack: &mut Vec<String> -> 適用中のテンプレート名のスタック。循環の検出に使用する
+// 戻り値：AppResult<()>
+fn extend_step(step: &mut Value, templates: &Mapping, stack: &mut Vec<String>) -> AppResult<()> {
+    let extends = match step
+        .as_mapping_mut()
+        .and_then(|step| step.remove("extends"))
+    {
+        Some(extends) => extends,
+        None => return Ok(()),
+    };
+    let name = extends
+        .as_str()
+        .ok_or_else(|| anyhow!("Invalid extends: {:?}", extends))?
+        .to_string();
+
+    if stack.contains(&name) {
+        return Err(anyhow!("Circular template: {}", name));
+    }
+
+    let mut base = templates
+        .get(name.as_str())
+        .cloned()
+        .ok_or_else(|| anyhow!("Template not found: {}", name))?;
+
+    stack.push(name);
+    extend_step(&mut base, templates, stack)?;
+    stack.pop();
+
+    merge_value(&mut base, step.clone());
+    *step = base;
+
+    Ok(())
+}
+
+// yamlの値を再帰的にマージする関数
+// 両方がマッピングの場合はキーごとにマージし、それ以外はoverlayで上書きする
+// 引数
+// - base: &mut Value -> マージ先の値。可変参照
+// - overlay: Value -> 上書きする値。所有権を移動する
+fn merge_value(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base_map), Value::Mapping(overlay_map)) => {
+            for (key, value) in overlay_map {
+                match base_map.get_mut(&key) {
+                    Some(base_value) => merge_value(base_value, value),
+                    None => {
+                        base_map.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+// テスト構成ファイルのトップレベルの値をマージする関数
+// - 配列(init, data)は連結する
+// - categoriesはカテゴリ名が重複した場合にエラーとする
+// - その他のマッピングはキーごとに上書きし、それ以外の値は上書きする
+// 引数
+// - target: &mut Mapping -> マージ先の値。可変参照
+// - source: Mapping -> マージする値。所有権を移動する
+// 戻り値：AppResult<()>
+fn merge_config(target: &mut Mapping, source: Mapping) -> AppResult<()> {
+    for (key, value) in source {
+        match (target.get_mut(&key), value) {
+            (Some(Value::Sequence(target_seq)), Value::Sequence(source_seq)) => {
+                target_seq.extend(source_seq);
+            }
+            (Some(Value::Mapping(target_map)), Value::Mapping(source_map)) => {
+                let is_categories = key.as_str() == Some("categories");
+                for (name, item) in source_map {
+                    if is_categories && target_map.contains_key(&name) {
+                        return Err(anyhow!(
+                            "Duplicate category: {}",
+                            name.as_str().unwrap_or_default()
+                        ));
+                    }
+                    target_map.insert(name, item);
+                }
+            }
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+// データファイルのパスの相対パスを基準ディレクトリからの絶対パスに変換する関数
+// データファイルに書かれたパスの解決に使用するため、基準ディレクトリを記録する
+// 引数
+// - data: &mut Value -> dataの値(文字列または文字列の配列)。可変参照
+// - base_dir: &Path -> 基準ディレクトリ。不変参照
+// - data_dirs: &mut HashMap<String, PathBuf> -> データファイルのパスと基準ディレクトリの連想配列。可変参照
+fn resolve_data_paths(data: &mut Value, base_dir: &Path, data_dirs: &mut HashMap<String, PathBuf>) {
+    match data {
+        Value::String(source) => {
+            if let Some((scheme, path)) = DATA_SCHEMES
+                .iter()
+                .find_map(|scheme| source.strip_prefix(scheme).map(|path| (*scheme, path)))
+            {
+                *source = format!("{}{}", scheme, base_dir.join(path).display());
+                data_dirs.insert(source.clone(), base_dir.to_path_buf());
+            }
+        }
+        Value::Sequence(sources) => {
+            for source in sources.iter_mut() {
+                resolve_data_paths(source, base_dir, data_dirs);
+            }
+        }
+        _ => {}
+    }
+}
+
+// テスト構成ファイルに書かれたファイルのパスを、基準ディレクトリからの絶対パスに変換する関数
+// 対象は次のパス。データファイルに書かれたパスは対象外とする
+// - openapi
+// - ステップとテンプレートのexpect_schema、graphql.query_file
+// - ステップに直接記述したデータのexpect_schema、body_file、マルチパートのパートのfile
+// 引数
+// - config_map: &mut Mapping -> テスト構成ファイルのyamlの値。可変参照
+// - base_dir: &Path -> 基準ディレクトリ。不変参照
+// 戻り値：AppResult<()> -> パスに含まれる環境変数が存在しない場合はエラー
+fn resolve_file_paths(config_map: &mut Mapping, base_dir: &Path) -> AppResult<()> {
+    if let Some(openapi) = config_map.get_mut("openapi") {
+        resolve_path(openapi, base_dir)?;
+    }
+
+    // マルチパートの判定に使用するため、同じファイルのテンプレートを参照する
+    let templates = match config_map.get("templates") {
+        Some(Value::Mapping(templates)) => templates.clone(),
+        _ => Mapping::new(),
+    };
+
+    let mut steps: Vec<&mut Value> = Vec::new();
+    for (key, value) in config_map.iter_mut() {
+        match (key.as_str(), value) {
+            (Some("templates"), Value::Mapping(templates)) => {
+                steps.extend(templates.iter_mut().map(|(_, template)| template));
+            }
+            (Some("init"), Value::Sequence(init)) => steps.extend(init.iter_mut()),
+            (Some("categories"), Value::Mapping(categories)) => {
+                for (_, category) in categories.iter_mut() {
+                    if let Some(Value::Sequence(category_steps)) = category.get_mut("steps") {
+                        steps.extend(category_steps.iter_mut());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for step in steps {
+        let step_body_type = declared_body_type(step, &templates, &mut Vec::new());
+        if let Some(schema) = step.get_mut("expect_schema") {
+            resolve_path(schema, base_dir)?;
+        }
+        if let Some(query_file) = step
+            .get_mut("graphql")
+            .and_then(|graphql| graphql.get_mut("query_file"))
+        {
+            resolve_path(query_file, base_dir)?;
+        }
+        if let Some(Value::Sequence(data)) = step.get_mut("data") {
+            for data in data.iter_mut() {
+                let body_type = data
+                    .get("body_type")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+                    .or_else(|| step_body_type.clone());
+                for key in ["expect_schema", "body_file"] {
+                    if let Some(value) = data.get_mut(key) {
+                        resolve_path(value, base_dir)?;
+                    }
+                }
+                // jsonのボディのfileはパスではないため、マルチパートの場合のみ解決する
+                if body_type.as_deref() == Some("multipart") {
+                    if let Some(Value::Mapping(body)) = data.get_mut("body") {
+                        for (_, part) in body.iter_mut() {
+                            if let Some(file) = part.get_mut("file") {
+                                resolve_path(file, base_dir)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ステップに記述されたボディの形式を取得する関数
+// ステップにない場合は、同じファイルのテンプレートをextendsで辿って取得する
+// 引数
+// - step: &Value -> ステップまたはテンプレートのyamlの値。不変参照
+// - templates: &Mapping -> 同じファイルのテンプレートの連想配列。不変参照
+// - stack: &mut Vec<String> -> 辿ったテンプレート名のスタック。循環の検出に使用する
+// 戻り値：Option<String> -> ボディの形式。見つからない場合はNone
+fn declared_body_type(
+    step: &Value,
+    templates: &Mapping,
+    stack: &mut Vec<String>,
+) -> Option<String> {
+    if let Some(body_type) = step.get("body_type").and_then(Value::as_str) {
+        return Some(body_type.to_string());
+    }
+    let name = step.get("extends").and_then(Value::as_str)?.to_string();
+    if stack.contains(&name) {
+        return None;
+    }
+    let template = templates.get(name.as_str())?;
+    stack.push(name);
+    declared_body_type(template, templates, stack)
+}
+
+// 文字列のパスを基準ディレクトリからの絶対パスに変換する関数
+// パスに含まれる環境変数は、絶対パスに変換する前に展開する
+// 引数
+// - value: &mut Value -> パスの値。文字列以外(インラインのスキーマなど)は変更しない。可変参照
+// - base_dir: &Path -> 基準ディレクトリ。不変参照
+// 戻り値：AppResult<()> -> パスに含まれる環境変数が存在しない場合はエラー
+fn resolve_path(value: &mut Value, base_dir: &Path) -> AppResult<()> {
+    if let Value::String(path) = value {
+        *path = base_dir.join(expand_env_vars(path)?).display().to_string();
+    }
+    Ok(())
+}
+
+// 文字列または文字列の配列のyamlの値を文字列の配列に変換する関数
+// 引数
+// - value: Value -> 変換する値。所有権を移動する
+// - field: &str -> エラーメッセージに使用するフィールド名
+// 戻り値：AppResult<Vec<String>> -> 文字列の配列をAppResultでラップしたもの
+fn value_to_strings(value: Value, field: &str) -> AppResult<Vec<String>> {
+    match value {
+        Value::String(s) => Ok(vec![s]),
+        Value::Sequence(seq) => seq
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s),
+                _ => Err(anyhow!("Invalid {}", field)),
+            })
+            .collect(),
+        _ => Err(anyhow!("Invalid {}", field)),
+    }
+}
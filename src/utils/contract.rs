@@ -0,0 +1,263 @@
+use anyhow::anyhow;
+use jsonschema::{Draft, JSONSchema};
+use regex::Regex;
+use serde_json::Value;
+use std::path::Path;
+
+use super::base_path;
+use super::body::RequestBody;
+use super::openapi::{json_media, OpenApiSpec, Operation};
+use super::schema::schema_errors;
+use super::types::AppResult;
+
+// OpenAPIドキュメントの契約を格納する構造体
+// テストのリクエストとレスポンスがドキュメントに適合するかを検証する
+#[derive(Debug)]
+pub struct Contract {
+    pub operations: Vec<ContractOperation>,
+    // ドキュメントの最初のサーバーのURLのパス(例: /v1)。末尾のスラッシュは除く
+    base_path: String,
+}
+
+// 契約のオペレーションを格納する構造体
+#[derive(Debug)]
+pub struct ContractOperation {
+    // 大文字のHTTPメソッド
+    pub method: String,
+    // ドキュメントに記述されたパス(例: /api/users/{id})
+    pub path: String,
+    // パスにマッチする正規表現
+    pattern: Regex,
+    // パスパラメータの数。複数のパスにマッチした場合は少ないものを優先する
+    param_count: usize,
+    // jsonのリクエストボディのスキーマ
+    request_schema: Option<JSONSchema>,
+    // リクエストボディが必須かどうか
+    request_required: bool,
+    // レスポンスのステータスコード(200, 4XX, default)とjsonのボディのスキーマ
+    responses: Vec<(String, Option<JSONSchema>)>,
+}
+
+impl Contract {
+    // OpenAPIドキュメントを読み込み、全てのスキーマをコンパイルする関数
+    // 引数：path: &Path -> OpenAPIドキュメントのパス。不変参照
+    // 戻り値：AppResult<Contract> -> 契約をAppResultでラップしたもの
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let spec = OpenApiSpec::load(path)?;
+        let operations = spec
+            .operations()?
+            .iter()
+            .map(|operation| compile_operation(&spec, operation))
+            .collect::<AppResult<Vec<ContractOperation>>>()?;
+        // サーバーのURLは相対パス(例: /v1)でも記述できる
+        let base_path = match spec.server_url() {
+            Some(url) if url.starts_with('/') => url.trim_end_matches('/').to_string(),
+            Some(url) => base_path(url),
+            None => String::new(),
+        };
+        Ok(Contract {
+            operations,
+            base_path,
+        })
+    }
+
+    // リクエストのメソッドとパスにマッチするオペレーションを返す関数
+    // 引数
+    // - method: &str -> HTTPメソッド
+    // - path: &str -> ベースURLのパスを含む、置換済みのリクエストのパス。クエリ文字列は無視する
+    // 戻り値：Option<usize> -> マッチしたオペレーションのインデックス。マッチしない場合はNone
+    pub fn find_operation(&self, method: &str, path: &str) -> Option<usize> {
+        let path = self.operation_path(path);
+        self.operations
+            .iter()
+            .enumerate()
+            .filter(|(_, operation)| {
+                operation.method.eq_ignore_ascii_case(method) && operation.pattern.is_match(path)
+            })
+            .min_by_key(|(_, operation)| operation.param_count)
+            .map(|(index, _)| index)
+    }
+
+    // リクエストとレスポンスがドキュメントに適合するか検証する関数
+    // 引数
+    // - method: &str -> HTTPメソッド
+    // - path: &str -> ベースURLのパスを含む、置換済みのリクエストのパス
+    // - request_body: Option<&RequestBody> -> 送信したリクエストボディ。jsonのボディのみスキーマで検証する
+    // - status: Option<u16> -> レスポンスのステータスコード。Noneの場合はステータスコードとレスポンスボディを検証しない
+    // - response_body: &str -> レスポンスボディ
+    // 戻り値：Vec<String> -> 契約違反の内容の配列。適合する場合は空
+    pub fn check(
+        &self,
+        method: &str,
+        path: &str,
+        request_body: Option<&RequestBody>,
+        status: Option<u16>,
+        response_body: &str,
+    ) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        let operation = match self.find_operation(method, path) {
+            Some(index) => &self.operations[index],
+            None => {
+                let path = self.operation_path(path);
+                if self.operations.iter().any(|o| o.pattern.is_match(path)) {
+                    violations.push(format!("undocumented method: {} {}", method, path));
+                } else {
+                    violations.push(format!("undocumented path: {}", path));
+                }
+                return violations;
+            }
+        };
+
+        // リクエストボディを検証する
+        match (request_body, &operation.request_schema) {
+            (Some(body), Some(schema)) => {
+                if let Some(errors) = body.as_json().and_then(|body| schema_errors(schema, body)) {
+                    violations.push(format!("request body: [{}]", errors));
+                }
+            }
+            (None, _) if operation.request_required => {
+                violations.push("request body: required but not sent".to_string());
+            }
+            _ => {}
+        }
+
+        // ステータスコードが記述されているか確認し、レスポンスボディを検証する
+        let Some(status) = status else {
+            return violations;
+        };
+        match operation.find_response(status) {
+            None => violations.push(format!(
+                "undocumented status: {} ({} {})",
+                status, operation.method, operation.path
+            )),
+            Some((_, Some(schema))) if !response_body.is_empty() => {
+                match serde_json::from_str::<Value>(response_body) {
+                    Ok(body) => {
+                        if let Some(errors) = schema_errors(schema, &body) {
+                            violations.push(format!("response body: [{}]", errors));
+                        }
+                    }
+                    Err(e) => violations.push(format!("response body is not json: {}", e)),
+                }
+            }
+            Some(_) => {}
+        }
+
+        violations
+    }
+
+    // リクエストのパスから、ドキュメントのパスと照合する部分を返す関数
+    // クエリ文字列と、サーバーのURLのパスの接頭辞を取り除く。接頭辞がない場合はそのまま照合する
+    // 引数：path: &str -> ベースURLのパスを含む、置換済みのリクエストのパス
+    // 戻り値：&str -> ドキュメントのパスと照合する部分
+    fn operation_path<'a>(&self, path: &'a str) -> &'a str {
+        let path = path.split('?').next().unwrap_or_default();
+        match path.strip_prefix(self.base_path.as_str()) {
+            Some("") => "/",
+            Some(rest) if rest.starts_with('/') => rest,
+            _ => path,
+        }
+    }
+}
+
+impl ContractOperation {
+    // ステータスコードに対応するレスポンスを返す関数
+    // ステータスコード、範囲(4XX)、defaultの順に探す
+    // 引数：status: u16 -> ステータスコード
+    // 戻り値：Option<&(String, Option<JSONSchema>)> -> レスポンスのキーとスキーマ。記述がない場合はNone
+    pub fn find_response(&self, status: u16) -> Option<&(String, Option<JSONSchema>)> {
+        let status_code = status.to_string();
+        let status_class = format!("{}XX", status / 100);
+        [status_code.as_str(), status_class.as_str(), "default"]
+            .iter()
+            .find_map(|key| {
+                self.responses
+                    .iter()
+                    .find(|(code, _)| code.eq_ignore_ascii_case(key))
+            })
+    }
+
+    // 記述されたレスポンスのキーを返す関数
+    // 戻り値：impl Iterator<Item = &str> -> レスポンスのキー(200, 4XX, default)のイテレータ
+    pub fn response_codes(&self) -> impl Iterator<Item = &str> {
+        self.responses.iter().map(|(code, _)| code.as_str())
+    }
+}
+
+// オペレーションのスキーマをコンパイルする関数
+// 3.0のドキュメントはDraft 4、それ以外はDraft 7のJSON Schemaとして扱う
+// 引数
+// - spec: &OpenApiSpec -> OpenAPIドキュメント。不変参照
+// - operation: &Operation -> オペレーション。不変参照
+// 戻り値：AppResult<ContractOperation> -> 契約のオペレーションをAppResultでラップしたもの
+fn compile_operation(spec: &OpenApiSpec, operation: &Operation) -> AppResult<ContractOperation> {
+    let draft = if spec.version().starts_with("3.0") {
+        Draft::Draft4
+    } else {
+        Draft::Draft7
+    };
+    let compile = |media: Option<&Value>| -> AppResult<Option<JSONSchema>> {
+        match media.and_then(|media| media.get("schema")) {
+            Some(schema) => {
+                let schema = spec.inline_schema(schema)?;
+                JSONSchema::options()
+                    .with_draft(draft)
+                    .compile(&schema)
+                    .map(Some)
+                    .map_err(|e| {
+                        anyhow!(
+                            "Invalid schema in OpenAPI document: {} {} ({})",
+                            operation.method,
+                            operation.path,
+                            e
+                        )
+                    })
+            }
+            None => Ok(None),
+        }
+    };
+
+    let request_body = match operation.operation.get("requestBody") {
+        Some(request_body) => Some(spec.resolve(request_body)?),
+        None => None,
+    };
+
+    let mut responses = Vec::new();
+    if let Some(map) = operation
+        .operation
+        .get("responses")
+        .and_then(Value::as_object)
+    {
+        for (code, response) in map {
+            let response = spec.resolve(response)?;
+            responses.push((code.clone(), compile(json_media(response))?));
+        }
+    }
+
+    // パスパラメータを一つのセグメントにマッチさせる
+    let mut pattern = String::from("^");
+    let mut param_count = 0;
+    for (index, part) in operation.path.split(['{', '}']).enumerate() {
+        if index % 2 == 0 {
+            pattern.push_str(&regex::escape(part));
+        } else {
+            pattern.push_str("[^/]+");
+            param_count += 1;
+        }
+    }
+    pattern.push('$');
+
+    Ok(ContractOperation {
+        method: operation.method.clone(),
+        path: operation.path.to_string(),
+        pattern: Regex::new(&pattern)?,
+        param_count,
+        request_schema: compile(request_body.and_then(json_media))?,
+        request_required: request_body
+            .and_then(|body| body.get("required"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        responses,
+    })
+}
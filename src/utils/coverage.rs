@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use super::contract::Contract;
+use super::types::OutputCoverage;
+
+// OpenAPIドキュメントのオペレーションとレスポンスのカバレッジを集計する構造体
+pub struct CoverageTracker<'a> {
+    contract: &'a Contract,
+    // 実行したオペレーションのインデックス
+    operations: HashSet<usize>,
+    // オペレーションのインデックスと、観測したステータスコードに対応するレスポンスのキー
+    responses: HashSet<(usize, String)>,
+}
+
+impl<'a> CoverageTracker<'a> {
+    // 構造体を生成する関数
+    // 引数：contract: &Contract -> 集計対象の契約。不変参照
+    // 戻り値：CoverageTracker
+    pub fn new(contract: &'a Contract) -> Self {
+        CoverageTracker {
+            contract,
+            operations: HashSet::new(),
+            responses: HashSet::new(),
+        }
+    }
+
+    // 実行したリクエストと観測したステータスコードを記録する関数
+    // 引数
+    // - method: &str -> HTTPメソッド
+    // - path: &str -> 置換済みのリクエストのパス
+    // - status: u16 -> レスポンスのステータスコード
+    pub fn record(&mut self, method: &str, path: &str, status: u16) {
+        let index = match self.contract.find_operation(method, path) {
+            Some(index) => index,
+            None => return,
+        };
+        self.operations.insert(index);
+        if let Some((code, _)) = self.contract.operations[index].find_response(status) {
+            self.responses.insert((index, code.to_uppercase()));
+        }
+    }
+
+    // カバレッジの集計結果を返す関数
+    // レスポンスはdefaultを除き、ステータスコードと範囲(4XX)を対象とする
+    // 戻り値：OutputCoverage -> カバレッジの集計結果
+    pub fn summary(&self) -> OutputCoverage {
+        let mut untested_operations = Vec::new();
+        let mut untested_responses = Vec::new();
+        let mut response_count = 0;
+
+        for (index, operation) in self.contract.operations.iter().enumerate() {
+            let name = format!("{} {}", operation.method, operation.path);
+            if !self.operations.contains(&index) {
+                untested_operations.push(name.clone());
+            }
+            for code in operation
+                .response_codes()
+                .filter(|code| !code.eq_ignore_ascii_case("default"))
+            {
+                response_count += 1;
+                if !self.responses.contains(&(index, code.to_uppercase())) {
+                    untested_responses.push(format!("{} {}", name, code));
+                }
+            }
+        }
+
+        let operation_count = self.contract.operations.len();
+        OutputCoverage {
+            operations: operation_count,
+            tested_operations: operation_count - untested_operations.len(),
+            operation_percent: percent(
+                operation_count - untested_operations.len(),
+                operation_count,
+            ),
+            responses: response_count,
+            tested_responses: response_count - untested_responses.len(),
+            response_percent: percent(response_count - untested_responses.len(), response_count),
+            untested_operations,
+            untested_responses,
+        }
+    }
+}
+
+// 割合をパーセントで返す関数
+// 引数
+// - tested: usize -> 実行した数
+// - total: usize -> 全体の数。0の場合は100%とする
+// 戻り値：f64 -> パーセント
+fn percent(tested: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        tested as f64 * 100.0 / total as f64
+    }
+}
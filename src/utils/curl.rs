@@ -0,0 +1,232 @@
+use reqwest::header::CONTENT_TYPE;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::body::{binary_content_type, PartValue, RequestBody};
+use super::types::{AppResult, FlattenStep, InputConfigration, InputDataMap};
+use super::{gen_flatten_step, interpolate, sorted_categories};
+
+// 秘匿情報を置き換える文字列
+const REDACTED: &str = "***";
+
+// 値を秘匿するヘッダ名
+const SECRET_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+// ヘッダ名やボディのキーに含まれる場合に値を秘匿する単語
+// 区切り文字(-, _)を除いて比較する
+const SECRET_WORDS: [&str; 5] = ["password", "passwd", "secret", "token", "apikey"];
+
+// 全てのカテゴリのステップをcurlコマンドに変換する関数
+// ログインが必要なカテゴリで、クッキーが取得されていない場合はコメントで示す
+// 引数
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// - input_data_map: &InputDataMap -> jsonデータの連想配列。不変参照
+// - cookie_map: &HashMap<String, String> -> クッキーの連想配列。不変参照
+// - redact: bool -> 秘匿情報を伏せるかどうか
+// 戻り値：AppResult<String> -> ステップ名のコメントを付けたcurlコマンドをAppResultでラップしたもの
+pub fn export_curl(
+    test_config: &InputConfigration,
+    input_data_map: &InputDataMap,
+    cookie_map: &HashMap<String, String>,
+    redact: bool,
+) -> AppResult<String> {
+    let mut commands: Vec<String> = Vec::new();
+
+    for (category_name, category) in sorted_categories(test_config) {
+        let cookie = category
+            .login
+            .as_ref()
+            .and_then(|login| cookie_map.get(login));
+
+        for (_, _, test_step) in gen_flatten_step(test_config, category, input_data_map)? {
+            let mut command = format!("# {}/{}\n", category_name, test_step.name);
+            // WebSocketのステップはcurlコマンドに変換できないため、コメントのみを出力する
+            if test_step.websocket.is_some() {
+                command.push_str("# websocket steps are not exported");
+                commands.push(command);
+                continue;
+            }
+            if let (Some(login), None) = (&category.login, cookie) {
+                command.push_str(&format!(
+                    "# requires the cookie of the init step: {}\n",
+                    login
+                ));
+            }
+            command.push_str(&to_curl(&test_step, cookie.map(String::as_str), redact));
+            commands.push(command);
+        }
+    }
+
+    Ok(commands.join("\n\n") + "\n")
+}
+
+// フラットされたステップをcurlコマンドに変換する関数
+// テスト実行時と同じく、ログインのクッキーより後にステップのヘッダを設定する
+// 引数
+// - test_step: &FlattenStep -> フラットされたステップの構造体。不変参照
+// - cookie: Option<&str> -> ログインで取得したクッキー
+// - redact: bool -> 秘匿情報を伏せるかどうか
+// 戻り値：String -> 複数行のcurlコマンド
+pub fn to_curl(test_step: &FlattenStep, cookie: Option<&str>, redact: bool) -> String {
+    let url = format!(
+        "{}{}",
+        test_step.base_url,
+        interpolate(&test_step.path, &test_step.variables)
+    );
+    let mut lines = vec![format!(
+        "curl -X {} {}",
+        test_step.method,
+        shell_quote(&url)
+    )];
+    // ストリーミングのステップは、受信した順に表示する
+    if test_step.stream.is_some() {
+        lines.push("-N".to_string());
+    }
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    // フォームとマルチパートのContent-Typeはcurlが設定する
+    let content_type = match &test_step.request_body {
+        Some(RequestBody::Json(_)) => Some("application/json".to_string()),
+        Some(RequestBody::Raw(_)) => Some("text/plain".to_string()),
+        Some(RequestBody::Binary { path, .. }) => Some(binary_content_type(path)),
+        _ => None,
+    };
+    if let Some(content_type) = content_type {
+        if !test_step.headers.contains_key(CONTENT_TYPE) {
+            headers.push(("Content-Type".to_string(), content_type));
+        }
+    }
+    if let Some(cookie) = cookie {
+        if !test_step.headers.contains_key("cookie") {
+            headers.push(("Cookie".to_string(), cookie.to_string()));
+        }
+    }
+    for (name, value) in test_step.headers.iter() {
+        headers.push((
+            name.to_string(),
+            String::from_utf8_lossy(value.as_bytes()).to_string(),
+        ));
+    }
+
+    for (name, value) in headers {
+        let value = if redact && is_secret_header(&name) {
+            REDACTED.to_string()
+        } else {
+            value
+        };
+        lines.push(format!(
+            "-H {}",
+            shell_quote(&format!("{}: {}", name, value))
+        ));
+    }
+
+    // 秘匿情報を表す名前の値を伏せる
+    let redact_field = |name: &str, value: &str| {
+        if redact && is_secret_name(name) {
+            REDACTED.to_string()
+        } else {
+            value.to_string()
+        }
+    };
+
+    match &test_step.request_body {
+        Some(RequestBody::Json(body)) => {
+            // ボディの最上位のキーはソートされ、入れ子のオブジェクトはデータに書かれた順に並ぶため、出力の順序は固定される
+            let mut body = body.clone();
+            if redact {
+                redact_value(&mut body);
+            }
+            lines.push(format!("--data-raw {}", shell_quote(&body.to_string())));
+        }
+        Some(RequestBody::Form(pairs)) => {
+            for (name, value) in pairs {
+                lines.push(format!(
+                    "--data-urlencode {}",
+                    shell_quote(&format!("{}={}", name, redact_field(name, value)))
+                ));
+            }
+        }
+        Some(RequestBody::Multipart(parts)) => {
+            for part in parts {
+                let mut field = match &part.value {
+                    PartValue::Text(text) => {
+                        format!("{}={}", part.name, redact_field(&part.name, text))
+                    }
+                    PartValue::File { path, .. } => format!("{}=@{}", part.name, path.display()),
+                };
+                if let Some(filename) = &part.filename {
+                    field.push_str(&format!(";filename={}", filename));
+                }
+                if let Some(content_type) = &part.content_type {
+                    field.push_str(&format!(";type={}", content_type));
+                }
+                // 属性のないテキストは、@や<で始まる値もそのまま送信する
+                let option = match (&part.value, &part.filename, &part.content_type) {
+                    (PartValue::Text(_), None, None) => "--form-string",
+                    _ => "-F",
+                };
+                lines.push(format!("{} {}", option, shell_quote(&field)));
+            }
+        }
+        Some(RequestBody::Raw(body)) => {
+            lines.push(format!("--data-raw {}", shell_quote(body)));
+        }
+        Some(RequestBody::Binary { path, .. }) => {
+            lines.push(format!(
+                "--data-binary {}",
+                shell_quote(&format!("@{}", path.display()))
+            ));
+        }
+        None => {}
+    }
+
+    lines.join(" \\\n  ")
+}
+
+// シェルで一つの引数として扱われるように、シングルクォートで囲む関数
+// 引数：value: &str -> クォートする文字列
+// 戻り値：String -> クォートした文字列
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// 秘匿情報を表す名前かどうかを判定する関数
+// 引数：name: &str -> ヘッダ名またはボディのキー
+// 戻り値：bool -> 秘匿情報を表す場合はtrue
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase().replace(['-', '_'], "");
+    SECRET_WORDS.iter().any(|word| name.contains(word))
+}
+
+// 値を秘匿するヘッダかどうかを判定する関数
+// 引数：name: &str -> ヘッダ名
+// 戻り値：bool -> 値を秘匿する場合はtrue
+fn is_secret_header(name: &str) -> bool {
+    SECRET_HEADERS
+        .iter()
+        .any(|header| header.eq_ignore_ascii_case(name))
+        || is_secret_name(name)
+}
+
+// jsonの値に含まれる秘匿情報を再帰的に伏せる関数
+// 引数：value: &mut Value -> jsonの値。可変参照
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_name(key) && !value.is_object() && !value.is_array() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::{anyhow, Context};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::env::{expand_env_vars, expand_json_value, expand_yaml_value};
+use super::types::{AppResult, InputData, InputDataMap};
+
+// データファイルのパスに指定できるスキーム
+pub const DATA_SCHEMES: [&str; 3] = ["json://", "yaml://", "csv://"];
+
+// データファイルを読み込む関数
+// 引数
+// - source: &str -> スキーム付きのデータファイルのパス。DATA_SCHEMESのいずれかを指定する
+// - base_dir: &Path -> 相対パスの基準となるディレクトリ(テスト構成ファイルのディレクトリ)
+// 戻り値：AppResult<InputDataMap> -> データの連想配列をAppResultでラップしたもの
+pub fn load_data_source(source: &str, base_dir: &Path) -> AppResult<InputDataMap> {
+    let (scheme, path) = DATA_SCHEMES
+        .iter()
+        .find_map(|scheme| source.strip_prefix(scheme).map(|path| (*scheme, path)))
+        .ok_or_else(|| anyhow!("Invalid data file path: {}", source))?;
+
+    // 相対パスはテスト構成ファイルのディレクトリを基準に解決する
+    let path = base_dir.join(path);
+
+    match scheme {
+        "json://" => load_json(&path),
+        "yaml://" => load_yaml(&path),
+        _ => load_csv(&path),
+    }
+    .with_context(|| format!("Failed to load data file: {}", path.display()))
+}
+
+// jsonファイルを読み込む関数
+// 引数：path: &Path -> jsonファイルのパス。不変参照
+// 戻り値：AppResult<InputDataMap> -> データの連想配列をAppResultでラップしたもの
+fn load_json(path: &Path) -> AppResult<InputDataMap> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut data_value: serde_json::Value = serde_json::from_reader(reader)?;
+    // 文字列に含まれる環境変数を展開する
+    expand_json_value(&mut data_value)?;
+    Ok(serde_json::from_value(data_value)?)
+}
+
+// yamlファイルを読み込む関数
+// 引数：path: &Path -> yamlファイルのパス。不変参照
+// 戻り値：AppResult<InputDataMap> -> データの連想配列をAppResultでラップしたもの
+fn load_yaml(path: &Path) -> AppResult<InputDataMap> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut data_value: serde_yaml::Value = serde_yaml::from_reader(reader)?;
+    // 文字列に含まれる環境変数を展開する
+    expand_yaml_value(&mut data_value)?;
+    Ok(serde_yaml::from_value(data_value)?)
+}
+
+// データをデータの連想配列に追加する関数
+// 引数
+// - input_data_map: &mut InputDataMap -> 追加先のデータの連想配列。可変参照
+// - key: String -> データのキー。所有権を移動する
+// - data: Vec<InputData> -> 追加するデータ。所有権を移動する
+// 戻り値：AppResult<()> -> 同じキーのデータが既に存在する場合はエラー
+pub fn insert_data(
+    input_data_map: &mut InputDataMap,
+    key: String,
+    data: Vec<InputData>,
+) -> AppResult<()> {
+    if input_data_map.contains_key(&key) {
+        return Err(anyhow!("Duplicate data key: {}", key));
+    }
+    input_data_map.insert(key, data);
+    Ok(())
+}
+
+// csvファイルを読み込み、各行をデータとして扱う関数
+// 列名はドット区切りで値の格納先を指定する
+// - body.user.name -> ボディの user.name
+// - query.id -> クエリの id
+// - headers.X-Request-Id -> ヘッダの X-Request-Id
+// - expect_status -> 予期するステータスコード
+// - max_duration_ms -> 所要時間の上限(ミリ秒)
+// - expect_schema -> レスポンスボディを検証するスキーマファイルのパス、またはjsonのスキーマ
+// - mock_response -> モックサーバーが返すレスポンスボディ(json)
+// - body_type, raw_body, body_file, content_type -> リクエストボディの形式、rawの文字列、送信するファイルのパス、Content-Type
+// - expect_body -> レスポンスボディの検証(json)
+// - variables.id -> GraphQLの変数の id
+// - body、query、variablesの列名の末尾に :string を付けた場合は、セルをjsonとして解釈せず文字列として格納する(例: body.zip:string)
+// - expect_errors, expect_data -> GraphQLのerrorsを予期するかどうか、dataの検証(json)
+// - messages -> WebSocketのステップで送受信するメッセージ(jsonの配列)
+// - expect_events -> ストリーミングのステップで受信するイベントの検証(json)
+// - ref_data -> データのキー。この列がない場合はファイル名(拡張子なし)をキーにする
+// 引数：path: &Path -> csvファイルのパス。不変参照
+// 戻り値：AppResult<InputDataMap> -> データの連想配列をAppResultでラップしたもの
+fn load_csv(path: &Path) -> AppResult<InputDataMap> {
+    let default_key = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut reader = csv::Reader::from_path(path)?;
+    let columns: Vec<String> = reader.headers()?.iter().map(String::from).collect();
+
+    let mut input_data_map = InputDataMap::new();
+    for (row_index, record) in reader.records().enumerate() {
+        let record = record?;
+        let mut key = default_key.clone();
+        let mut data = Map::new();
+
+        for (column, cell) in columns.iter().zip(record.iter()) {
+            // 空のセルは値が指定されていないものとして扱う
+            if cell.is_empty() {
+                continue;
+            }
+            let cell = expand_env_vars(cell)?;
+
+            // :stringが付いた列は、数値や真偽値に見えるセルも文字列として扱う
+            let (name, as_string) = match column.strip_suffix(":string") {
+                Some(name) => (name, true),
+                None => (column.as_str(), false),
+            };
+
+            match name.split_once('.') {
+                Some((section @ ("body" | "query" | "variables"), field)) => {
+                    let fields: Vec<&str> = field.split('.').collect();
+                    let target = data
+                        .entry(section)
+                        .or_insert_with(|| Value::Object(Map::new()));
+                    let value = if as_string {
+                        Value::String(cell)
+                    } else {
+                        parse_cell(&cell)
+                    };
+                    set_value(target, &fields, value).map_err(|e| {
+                        anyhow!("{} (row: {}, column: {})", e, row_index + 1, column)
+                    })?;
+                }
+                // :stringは、body、query、variablesの列にのみ指定できる
+                _ if as_string => return Err(anyhow!("Invalid csv column: {}", column)),
+                // ヘッダ名にはドットが含まれる可能性があるため、そのまま使用する
+                Some(("headers", name)) => {
+                    data.entry("headers")
+                        .or_insert_with(|| Value::Object(Map::new()))
+                        .as_object_mut()
+                        .unwrap()
+                        .insert(name.to_string(), Value::String(cell));
+                }
+                // expect_statusには数値、"2xx"のようなクラス、またはjsonの配列を指定できる
+                None if matches!(
+                    column.as_str(),
+                    "expect_status"
+                        | "max_duration_ms"
+                        | "expect_schema"
+                        | "mock_response"
+                        | "expect_body"
+                        | "expect_errors"
+                        | "expect_data"
+                        | "messages"
+                        | "expect_events"
+                ) =>
+                {
+                    data.insert(column.to_string(), parse_cell(&cell));
+                }
+                // 文字列の列はjsonとして解釈せず、そのまま使用する
+                None if matches!(
+                    column.as_str(),
+                    "body_type" | "raw_body" | "body_file" | "content_type"
+                ) =>
+                {
+                    data.insert(column.to_string(), Value::String(cell));
+                }
+                None if column == "ref_data" => key = cell,
+                _ => return Err(anyhow!("Invalid csv column: {}", column)),
+            }
+        }
+
+        let input_data: InputData = serde_json::from_value(Value::Object(data))
+            .map_err(|e| anyhow!("{} (row: {})", e, row_index + 1))?;
+        input_data_map.entry(key).or_default().push(input_data);
+    }
+
+    Ok(input_data_map)
+}
+
+// csvのセルの値をjsonの値に変換する関数
+// jsonとして解釈できる場合はその値を、できない場合は文字列を返す
+// 引数：cell: &str -> セルの値。不変参照
+// 戻り値：Value -> 変換したjsonの値
+fn parse_cell(cell: &str) -> Value {
+    serde_json::from_str(cell).unwrap_or_else(|_| Value::String(cell.to_string()))
+}
+
+// ドット区切りのフィールドに値を格納する関数
+// 引数
+// - target: &mut Value -> 格納先のjsonの値。可変参照
+// - fields: &[&str] -> フィールド名のスライス
+// - value: Value -> 格納する値。所有権を移動する
+// 戻り値：AppResult<()> -> 格納先がオブジェクトでない場合はエラー
+fn set_value(target: &mut Value, fields: &[&str], value: Value) -> AppResult<()> {
+    let object = target
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Conflicting csv column"))?;
+
+    match fields {
+        [field] => {
+            object.insert(field.to_string(), value);
+            Ok(())
+        }
+        [field, rest @ ..] => set_value(
+            object
+                .entry(field.to_string())
+                .or_insert_with(|| Value::Object(Map::new())),
+            rest,
+            value,
+        ),
+        [] => Err(anyhow!("Empty csv column")),
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::anyhow;
+use regex::Regex;
+use std::env;
+use std::sync::OnceLock;
+
+use super::types::{AppResult, InputConfigration};
+
+// ${NAME} または ${NAME:-default} にマッチする正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn env_var_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\$\{(\w+)(?::-([^}]*))?\}").unwrap())
+}
+
+// 文字列中の ${NAME} を環境変数の値で展開する関数
+// 引数：text: &str -> 展開対象の文字列。不変参照
+// 戻り値：AppResult<String> -> 展開後の文字列。環境変数が存在せず、デフォルト値もない場合はエラー
+pub fn expand_env_vars(text: &str) -> AppResult<String> {
+    let mut missing: Vec<String> = Vec::new();
+
+    let expanded = env_var_regex().replace_all(text, |captures: &regex::Captures| {
+        match (env::var(&captures[1]), captures.get(2)) {
+            (Ok(value), _) => value,
+            (Err(_), Some(default)) => default.as_str().to_string(),
+            (Err(_), None) => {
+                missing.push(captures[1].to_string());
+                "".to_string()
+            }
+        }
+    });
+
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "Environment variable not found: {}",
+            missing.join(", ")
+        ));
+    }
+
+    Ok(expanded.to_string())
+}
+
+// yamlの値に含まれる全ての文字列の ${NAME} を展開する関数
+// 引数：value: &mut serde_yaml::Value -> 展開対象のyamlの値。可変参照
+// 戻り値：AppResult<()>
+pub fn expand_yaml_value(value: &mut serde_yaml::Value) -> AppResult<()> {
+    match value {
+        serde_yaml::Value::String(s) => *s = expand_env_vars(s)?,
+        serde_yaml::Value::Sequence(seq) => {
+            for item in seq.iter_mut() {
+                expand_yaml_value(item)?;
+            }
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (_, item) in map.iter_mut() {
+                expand_yaml_value(item)?;
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => expand_yaml_value(&mut tagged.value)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+// jsonの値に含まれる全ての文字列の ${NAME} を展開する関数
+// 引数：value: &mut serde_json::Value -> 展開対象のjsonの値。可変参照
+// 戻り値：AppResult<()>
+pub fn expand_json_value(value: &mut serde_json::Value) -> AppResult<()> {
+    match value {
+        serde_json::Value::String(s) => *s = expand_env_vars(s)?,
+        serde_json::Value::Array(array) => {
+            for item in array.iter_mut() {
+                expand_json_value(item)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (_, item) in map.iter_mut() {
+                expand_json_value(item)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// 指定された環境の設定をテスト構成ファイルの構造体に適用する関数
+// 引数
+// - test_config: &mut InputConfigration -> テスト構成ファイルの構造体。可変参照
+// - env_name: &str -> 適用する環境の名前。不変参照
+// 戻り値：AppResult<()>
+pub fn apply_environment(test_config: &mut InputConfigration, env_name: &str) -> AppResult<()> {
+    let environment = test_config
+        .environments
+        .remove(env_name)
+        .ok_or_else(|| anyhow!("Environment not found: {}", env_name))?;
+
+    if let Some(base_url) = environment.base_url {
+        test_config.base_url = base_url;
+    }
+    test_config.services.extend(environment.services);
+    test_config.variables.extend(environment.variables);
+    for (key, value) in environment.headers {
+        // ヘッダ名は大文字小文字を区別せずに上書きする
+        test_config
+            .headers
+            .retain(|name, _| !name.eq_ignore_ascii_case(&key));
+        test_config.headers.insert(key, value);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,354 @@
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::distributions::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::types::{AppResult, InputConfigration, InputData, InputDataMap};
+use super::value_to_string;
+
+// fakerで使用する名前の一覧
+const FIRST_NAMES: [&str; 16] = [
+    "James", "Mary", "John", "Patricia", "Robert", "Jennifer", "Michael", "Linda", "Haruto", "Yui",
+    "Sota", "Hina", "Ren", "Aoi", "Yuto", "Sakura",
+];
+const LAST_NAMES: [&str; 16] = [
+    "Smith",
+    "Johnson",
+    "Williams",
+    "Brown",
+    "Jones",
+    "Miller",
+    "Davis",
+    "Wilson",
+    "Sato",
+    "Suzuki",
+    "Takahashi",
+    "Tanaka",
+    "Watanabe",
+    "Ito",
+    "Yamamoto",
+    "Nakamura",
+];
+const WORDS: [&str; 16] = [
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet",
+    "kilo", "lima", "mike", "november", "oscar", "papa",
+];
+
+// {{ expr }} にマッチする正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn expression_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*([^{}]*?)\s*\}\}").unwrap())
+}
+
+// 関数呼び出し name(arg, ...) にマッチする正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn call_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([\w.]+)(?:\((.*)\))?$").unwrap())
+}
+
+// データ生成用の関数を評価する構造体
+// シードが指定された場合は、同じシードで同じ値を生成する
+#[derive(Debug)]
+pub struct Generator {
+    rng: StdRng,
+    seq: u64,
+    // nowに使用する時刻。指定がない場合は評価した時刻を使用する
+    now: Option<DateTime<Utc>>,
+}
+
+// 既定値はランダムなシードを使用する
+impl Default for Generator {
+    fn default() -> Self {
+        Generator::new(None)
+    }
+}
+
+impl Generator {
+    // 構造体を生成する関数
+    // 引数：seed: Option<u64> -> 乱数のシード。指定がない場合はランダムなシードを使用する
+    // 戻り値：Generator
+    pub fn new(seed: Option<u64>) -> Self {
+        Generator {
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            seq: 0,
+            now: None,
+        }
+    }
+
+    // nowに使用する時刻を固定する関数
+    // カセットファイルの記録とリプレイで、同じリクエストを生成するために使用する
+    // 引数：now: DateTime<Utc> -> nowに使用する時刻
+    pub fn fix_now(&mut self, now: DateTime<Utc>) {
+        self.now = Some(now);
+    }
+
+    // jsonの値に含まれる全ての文字列の {{ expr }} を評価する関数
+    // 文字列全体が一つの式の場合は、式の結果の型(数値など)をそのまま使用する
+    // 引数：value: &mut Value -> 評価対象のjsonの値。可変参照
+    // 戻り値：AppResult<()>
+    pub fn render_value(&mut self, value: &mut Value) -> AppResult<()> {
+        match value {
+            Value::String(s) => *value = self.render(s)?,
+            Value::Array(array) => {
+                for item in array.iter_mut() {
+                    self.render_value(item)?;
+                }
+            }
+            Value::Object(map) => {
+                // serde_jsonのMapはデータに書かれた順に並んでいるため、評価の順序は常に同じになる
+                for (_, item) in map.iter_mut() {
+                    self.render_value(item)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // 連想配列の値の {{ expr }} を評価する関数
+    // シードによる再現性を保つため、キーの順に評価する
+    // 引数：map: &mut HashMap<String, Value> -> 評価対象の連想配列。可変参照
+    // 戻り値：AppResult<()>
+    pub fn render_map(&mut self, map: &mut HashMap<String, Value>) -> AppResult<()> {
+        let mut keys: Vec<String> = map.keys().cloned().collect();
+        keys.sort();
+        for key in keys {
+            self.render_value(map.get_mut(&key).unwrap())?;
+        }
+        Ok(())
+    }
+
+    // ヘッダの連想配列の値の {{ expr }} を評価する関数
+    // 引数：headers: &mut HashMap<String, String> -> 評価対象のヘッダの連想配列。可変参照
+    // 戻り値：AppResult<()>
+    pub fn render_headers(&mut self, headers: &mut HashMap<String, String>) -> AppResult<()> {
+        let mut keys: Vec<String> = headers.keys().cloned().collect();
+        keys.sort();
+        for key in keys {
+            let value = headers.get_mut(&key).unwrap();
+            *value = value_to_string(&self.render(value)?);
+        }
+        Ok(())
+    }
+
+    // データの {{ expr }} を評価する関数
+    // body、query、headers、GraphQLのvariables、raw_body、WebSocketで送信するメッセージを評価する
+    // 引数：data: &mut InputData -> 評価対象のデータ。可変参照
+    // 戻り値：AppResult<()>
+    pub fn render_data(&mut self, data: &mut InputData) -> AppResult<()> {
+        if let Some(body) = data.body.as_mut() {
+            self.render_map(body)?;
+        }
+        if let Some(query) = data.query.as_mut() {
+            self.render_map(query)?;
+        }
+        if let Some(headers) = data.headers.as_mut() {
+            self.render_headers(headers)?;
+        }
+        if let Some(variables) = data.variables.as_mut() {
+            self.render_map(variables)?;
+        }
+        if let Some(raw_body) = data.raw_body.as_mut() {
+            *raw_body = value_to_string(&self.render(raw_body)?);
+        }
+        // WebSocketで送信するメッセージのみ評価し、受信するメッセージの検証は評価しない
+        for message in data.messages.iter_mut().flatten() {
+            if let Some(send) = message.send.as_mut() {
+                self.render_value(send)?;
+            }
+        }
+        Ok(())
+    }
+
+    // 文字列の {{ expr }} を評価する関数
+    // 引数：text: &str -> 評価対象の文字列。不変参照
+    // 戻り値：AppResult<Value> -> 評価後の値をAppResultでラップしたもの
+    pub fn render(&mut self, text: &str) -> AppResult<Value> {
+        // 文字列全体が一つの式の場合は、式の結果をそのまま返す
+        if let Some(captures) = expression_regex().captures(text) {
+            if captures[0].len() == text.len() {
+                return self.evaluate(&captures[1]);
+            }
+        }
+
+        let mut result = String::new();
+        let mut last = 0;
+        for captures in expression_regex().captures_iter(text) {
+            let matched = captures.get(0).unwrap();
+            result.push_str(&text[last..matched.start()]);
+            result.push_str(&value_to_string(&self.evaluate(&captures[1])?));
+            last = matched.end();
+        }
+        result.push_str(&text[last..]);
+
+        Ok(Value::String(result))
+    }
+
+    // 式を評価する関数
+    // 式は「関数名(引数, ...) | フィルタ」の形式で記述する
+    // 引数：expression: &str -> 評価する式。不変参照
+    // 戻り値：AppResult<Value> -> 評価結果をAppResultでラップしたもの
+    fn evaluate(&mut self, expression: &str) -> AppResult<Value> {
+        let (call, filter) = match expression.split_once('|') {
+            Some((call, filter)) => (call.trim(), Some(filter.trim())),
+            None => (expression.trim(), None),
+        };
+
+        let captures = call_regex()
+            .captures(call)
+            .ok_or_else(|| anyhow!("Invalid template expression: {}", expression))?;
+        let name = &captures[1];
+        let args: Vec<&str> = captures
+            .get(2)
+            .map(|args| {
+                args.as_str()
+                    .split(',')
+                    .map(|arg| arg.trim().trim_matches(|c| c == '"' || c == '\''))
+                    .filter(|arg| !arg.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let value = match name {
+            "uuid" => {
+                let bytes: [u8; 16] = self.rng.gen();
+                Value::String(
+                    uuid::Builder::from_random_bytes(bytes)
+                        .into_uuid()
+                        .to_string(),
+                )
+            }
+            "seq" => {
+                self.seq += 1;
+                Value::from(self.seq)
+            }
+            "now" => {
+                let now = self.now.unwrap_or_else(Utc::now);
+                match filter.unwrap_or("rfc3339") {
+                    "rfc3339" => Value::String(now.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                    "unix" => Value::from(now.timestamp()),
+                    "unix_ms" => Value::from(now.timestamp_millis()),
+                    "date" => Value::String(now.format("%Y-%m-%d").to_string()),
+                    filter => return Err(anyhow!("Unknown template filter: {}", filter)),
+                }
+            }
+            "random.int" => {
+                let (min, max) = parse_range(&args, expression)?;
+                Value::from(self.rng.gen_range(min..=max))
+            }
+            "random.string" => {
+                let length = match args.first() {
+                    Some(length) => length
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid template arguments: {}", expression))?,
+                    None => 16,
+                };
+                let s: String = (&mut self.rng)
+                    .sample_iter(&Alphanumeric)
+                    .take(length)
+                    .map(char::from)
+                    .collect();
+                Value::String(s)
+            }
+            "faker.first_name" => Value::String(self.pick(&FIRST_NAMES).to_string()),
+            "faker.last_name" => Value::String(self.pick(&LAST_NAMES).to_string()),
+            "faker.name" => Value::String(format!(
+                "{} {}",
+                self.pick(&FIRST_NAMES),
+                self.pick(&LAST_NAMES)
+            )),
+            "faker.username" => Value::String(format!(
+                "{}{}",
+                self.pick(&FIRST_NAMES).to_lowercase(),
+                self.rng.gen_range(1000..10000)
+            )),
+            "faker.email" => Value::String(format!(
+                "{}.{}{}@example.com",
+                self.pick(&FIRST_NAMES).to_lowercase(),
+                self.pick(&LAST_NAMES).to_lowercase(),
+                self.rng.gen_range(1000..10000)
+            )),
+            "faker.word" => Value::String(self.pick(&WORDS).to_string()),
+            _ => return Err(anyhow!("Unknown template function: {}", name)),
+        };
+
+        // now以外の関数にはフィルタを指定できない
+        if name != "now" {
+            if let Some(filter) = filter {
+                return Err(anyhow!("Unknown template filter: {}", filter));
+            }
+        }
+
+        Ok(value)
+    }
+
+    // 配列からランダムに要素を選ぶ関数
+    // 引数：items: &[&'static str] -> 選択肢の配列
+    // 戻り値：&'static str -> 選ばれた要素
+    fn pick(&mut self, items: &[&'static str]) -> &'static str {
+        items[self.rng.gen_range(0..items.len())]
+    }
+}
+
+// テスト構成ファイルとデータの {{ expr }} が評価できるかチェックする関数
+// 値はフラットされたステップごとに評価するため、ここでは評価した値を使用しない
+// 引数
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// - input_data_map: &InputDataMap -> データの連想配列。不変参照
+// 戻り値：AppResult<()> -> 評価できない式がある場合はエラー
+pub fn check_generators(
+    test_config: &InputConfigration,
+    input_data_map: &InputDataMap,
+) -> AppResult<()> {
+    let mut generator = Generator::new(Some(0));
+
+    for (key, data) in input_data_map.iter() {
+        for data in data.iter() {
+            generator
+                .render_data(&mut data.clone())
+                .with_context(|| format!("Invalid data: {}", key))?;
+        }
+    }
+
+    let headers = std::iter::once(&test_config.headers)
+        .chain(test_config.init.iter().map(|init_step| &init_step.headers))
+        .chain(test_config.categories.values().flat_map(|category| {
+            std::iter::once(&category.headers)
+                .chain(category.steps.iter().map(|step| &step.headers))
+        }));
+    for headers in headers {
+        generator.render_headers(&mut headers.clone())?;
+    }
+
+    Ok(())
+}
+
+// 範囲の引数を解析する関数
+// 引数
+// - args: &[&str] -> 引数の配列
+// - expression: &str -> エラーメッセージに使用する式
+// 戻り値：AppResult<(i64, i64)> -> 最小値と最大値のタプル
+fn parse_range(args: &[&str], expression: &str) -> AppResult<(i64, i64)> {
+    let invalid = || anyhow!("Invalid template arguments: {}", expression);
+    match args {
+        [min, max] => {
+            let min: i64 = min.parse().map_err(|_| invalid())?;
+            let max: i64 = max.parse().map_err(|_| invalid())?;
+            if min > max {
+                return Err(invalid());
+            }
+            Ok((min, max))
+        }
+        _ => Err(invalid()),
+    }
+}
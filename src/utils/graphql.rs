@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Context};
+use jsonpath_lib::Compiled;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use super::body::RequestBody;
+use super::sorted_object;
+use super::types::{AppResult, InputData, InputGraphql};
+
+// GraphQLのレスポンスの検証を格納する構造体
+#[derive(Debug, Clone)]
+pub struct GraphqlAssertion {
+    // errorsが含まれることを予期するかどうか
+    expect_errors: bool,
+    // dataを起点とするJSONPathと、コンパイルしたJSONPath、予期する値
+    expect_data: Vec<(String, Compiled, Value)>,
+}
+
+// GraphQLのリクエストボディを生成する関数
+// クエリ、データの変数、オペレーション名をjsonのボディにまとめる
+// 引数
+// - graphql: &InputGraphql -> ステップのGraphQLのリクエスト。不変参照
+// - data: Option<&InputData> -> 変数を含むデータ
+// - base_dir: &Path -> クエリファイルのパスの基準となるディレクトリ。不変参照
+// 戻り値：AppResult<RequestBody> -> jsonのリクエストボディをAppResultでラップしたもの
+pub fn gen_graphql_body(
+    graphql: &InputGraphql,
+    data: Option<&InputData>,
+    base_dir: &Path,
+) -> AppResult<RequestBody> {
+    let query = match (&graphql.query, &graphql.query_file) {
+        (Some(query), _) => query.clone(),
+        (None, Some(query_file)) => {
+            let path = base_dir.join(query_file);
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read query file: {}", path.display()))?
+        }
+        (None, None) => return Err(anyhow!("graphql.query or graphql.query_file is required")),
+    };
+
+    let mut body = Map::new();
+    body.insert("query".to_string(), Value::String(query));
+    if let Some(variables) = data.and_then(|data| data.variables.as_ref()) {
+        body.insert("variables".to_string(), sorted_object(variables));
+    }
+    if let Some(operation_name) = &graphql.operation_name {
+        body.insert(
+            "operationName".to_string(),
+            Value::String(operation_name.clone()),
+        );
+    }
+
+    Ok(RequestBody::Json(Value::Object(body)))
+}
+
+// データからGraphQLのレスポンスの検証を生成する関数
+// JSONPathは$から始まらない場合、$.を補う
+// 引数：data: &InputData -> データ。不変参照
+// 戻り値：AppResult<GraphqlAssertion> -> 検証をAppResultでラップしたもの
+pub fn compile_graphql_assertion(data: &InputData) -> AppResult<GraphqlAssertion> {
+    let expect_data = data
+        .expect_data
+        .iter()
+        .flatten()
+        .map(|(path, value)| {
+            let full_path = if path.starts_with('$') {
+                path.clone()
+            } else {
+                format!("$.{}", path)
+            };
+            let compiled = Compiled::compile(&full_path)
+                .map_err(|e| anyhow!("Invalid JSONPath: {} ({})", path, e))?;
+            Ok((path.clone(), compiled, value.clone()))
+        })
+        .collect::<AppResult<Vec<(String, Compiled, Value)>>>()?;
+
+    Ok(GraphqlAssertion {
+        expect_errors: data.expect_errors.unwrap_or(false),
+        expect_data,
+    })
+}
+
+impl GraphqlAssertion {
+    // GraphQLのレスポンスボディを検証する関数
+    // JSONPathに複数の値がマッチした場合は、配列として比較する
+    // 引数：body: &str -> レスポンスボディ
+    // 戻り値：Vec<String> -> 検証に失敗した内容の配列。全て成功した場合は空
+    pub fn check(&self, body: &str) -> Vec<String> {
+        let body: Value = match serde_json::from_str(body) {
+            Ok(body) => body,
+            Err(e) => return vec![format!("response body is not json: {}", e)],
+        };
+
+        let mut failures = Vec::new();
+
+        // errorsの有無を確認する
+        let messages: Vec<String> = body
+            .get("errors")
+            .and_then(Value::as_array)
+            .map(|errors| {
+                errors
+                    .iter()
+                    .map(|error| match error.get("message") {
+                        Some(Value::String(message)) => message.clone(),
+                        _ => error.to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        match (self.expect_errors, messages.is_empty()) {
+            (false, false) => failures.push(format!("graphql errors: [{}]", messages.join("; "))),
+            (true, true) => failures.push("graphql errors: expected but not returned".to_string()),
+            _ => {}
+        }
+
+        // dataを起点にJSONPathの値を比較する
+        let data = body.get("data").unwrap_or(&Value::Null);
+        for (path, compiled, expect) in self.expect_data.iter() {
+            let actual = match compiled.select(data) {
+                Ok(values) if values.is_empty() => {
+                    failures.push(format!("data {}: not found", path));
+                    continue;
+                }
+                Ok(values) if values.len() == 1 => values[0].clone(),
+                Ok(values) => json!(values),
+                Err(e) => {
+                    failures.push(format!("data {}: {}", path, e));
+                    continue;
+                }
+            };
+            if actual != *expect {
+                failures.push(format!("data {}: {}, expect: {}", path, actual, expect));
+            }
+        }
+
+        failures
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Context;
+use indexmap::IndexMap;
+use serde_json::to_writer_pretty;
+use std::fs::File;
+use std::path::Path;
+
+use super::types::{AppResult, InputConfigration, InputData, InputStep};
+
+pub mod har;
+pub mod openapi;
+pub mod postman;
+
+// インポートしたデータの連想配列を定義する
+// 出力するデータファイルをインポート元の順序で並べるため、順序を保持する
+pub type ImportedDataMap = IndexMap<String, Vec<InputData>>;
+
+// インポートしたテスト構成ファイルとデータファイルを書き出す関数
+// 引数
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// - data_map: &ImportedDataMap -> データの連想配列。不変参照
+// - output_yml_path: &str -> 出力するテスト構成ファイルのパス
+// - output_data_path: &str -> 出力するデータファイルのパス
+// 戻り値：AppResult<()>
+pub fn write_suite(
+    test_config: &InputConfigration,
+    data_map: &ImportedDataMap,
+    output_yml_path: &str,
+    output_data_path: &str,
+) -> AppResult<()> {
+    println!("[*] Writing config file... -> [{}]", output_yml_path);
+    let config_file = File::create(output_yml_path)
+        .with_context(|| format!("Failed to create config file: {}", output_yml_path))?;
+    serde_yaml::to_writer(config_file, test_config)?;
+
+    println!("[*] Writing data file... -> [{}]", output_data_path);
+    let data_file = File::create(output_data_path)
+        .with_context(|| format!("Failed to create data file: {}", output_data_path))?;
+    to_writer_pretty(data_file, data_map)?;
+
+    Ok(())
+}
+
+// テスト構成ファイルに記述するデータファイルのパスを返す関数
+// データファイルのパスはテスト構成ファイルのディレクトリを基準に解決されるため、
+// 同じディレクトリに出力する場合はファイル名、それ以外は絶対パスを使用する
+// 引数
+// - output_yml_path: &str -> 出力するテスト構成ファイルのパス
+// - output_data_path: &str -> 出力するデータファイルのパス
+// 戻り値：AppResult<String> -> json://から始まるデータファイルのパスをAppResultでラップしたもの
+pub fn data_source(output_yml_path: &str, output_data_path: &str) -> AppResult<String> {
+    let config_path = std::path::absolute(output_yml_path)?;
+    let data_path = std::path::absolute(output_data_path)?;
+
+    let path = if config_path.parent() == data_path.parent() {
+        data_path
+            .file_name()
+            .map(Path::new)
+            .unwrap_or(&data_path)
+            .to_path_buf()
+    } else {
+        data_path
+    };
+
+    Ok(format!("json://{}", path.display()))
+}
+
+// パスとHTTPメソッドからステップ名を生成する関数
+// パスの区切り文字と記号で単語を区切り、各単語の先頭を大文字にして連結する
+// 例: /api/user/me, GET -> ApiUserMe(GET)
+// 引数
+// - path: &str -> リクエストのパス
+// - method: &str -> HTTPメソッド
+// 戻り値：String -> ステップ名
+pub fn step_name(path: &str, method: &str) -> String {
+    let name: String = path
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    format!(
+        "{}({})",
+        if name.is_empty() { "Root" } else { &name },
+        method.to_uppercase()
+    )
+}
+
+// カテゴリ内で重複しないステップ名を返す関数
+// 同じ名前のステップが既に存在する場合は連番を付与する
+// 引数
+// - steps: &[InputStep] -> カテゴリのステップの配列。不変参照
+// - name: String -> ステップ名。所有権を移動する
+// 戻り値：String -> 重複しないステップ名
+pub fn unique_step_name(steps: &[InputStep], name: String) -> String {
+    let mut unique_name = name.clone();
+    let mut suffix = 2;
+    while steps.iter().any(|step| step.name == unique_name) {
+        unique_name = format!("{}_{}", name, suffix);
+        suffix += 1;
+    }
+    unique_name
+}
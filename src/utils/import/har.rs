@@ -0,0 +1,504 @@
+use anyhow::{anyhow, Context};
+use regex::Regex;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::OnceLock;
+
+use super::{data_source, step_name, unique_step_name, ImportedDataMap};
+use crate::utils::types::{
+    AppResult, BodyType, ExpectStatus, InputCaterogy, InputConfigration, InputData, InputOption,
+    InputStep, StatusPattern,
+};
+
+// HARファイルの構造体を定義する
+#[derive(Debug, Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarLog {
+    #[serde(default)]
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarEntry {
+    request: HarRequest,
+    response: HarResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(rename = "postData")]
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarPostData {
+    #[serde(rename = "mimeType", default)]
+    mime_type: String,
+    text: Option<String>,
+    // フォームの値。textがない場合に使用する
+    #[serde(default)]
+    params: Vec<HarParam>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarParam {
+    name: String,
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarResponse {
+    status: u16,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarHeader {
+    name: String,
+}
+
+// HARのインポートの絞り込み条件を定義する
+pub struct HarFilter {
+    // 対象とするホスト名。空の場合は全てのホストを対象とする
+    pub hosts: Vec<String>,
+    // 対象とするパスの接頭辞
+    pub path_prefix: Option<String>,
+}
+
+// IDとみなすパスのセグメントにマッチする正規表現を取得する関数
+// 数値、UUID、16文字以上の16進数をIDとみなす
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn id_segment_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^(\d+|[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}|[0-9a-fA-F]{16,})$")
+            .unwrap()
+    })
+}
+
+// ログインのリクエストのパスにマッチする正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn login_path_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)(login|log-in|signin|sign-in|sign_in|auth|session|token)").unwrap()
+    })
+}
+
+// 記録されたリクエストを変換したものを格納する構造体
+struct Recorded {
+    // リクエスト先のオリジン
+    origin: String,
+    // IDをプレースホルダにしたパス(クエリ文字列を含む)
+    path: String,
+    method: String,
+    category_name: String,
+    input_data: InputData,
+}
+
+// HARファイルからテスト構成ファイルとデータを生成する関数
+// - 同じメソッドとパスの形(IDのセグメントとクエリ文字列の値を除く)のリクエストを一つのステップにし、記録されたリクエストをそれぞれデータにする
+// - パスの最初のセグメント(api、v1などを除く)をカテゴリ名にする
+// - ログインのリクエスト(パスにloginなどを含み、Set-Cookieを返したPOST)はinitステップにし、以降のリクエストのカテゴリのloginに指定する
+// - ログインより前のリクエストを含むカテゴリは、ログイン後のリクエストを「カテゴリ名_login」のカテゴリに分ける
+// 引数
+// - har_path: &str -> HARファイルのパス
+// - filter: &HarFilter -> 絞り込み条件。不変参照
+// - output_yml_path: &str -> 出力するテスト構成ファイルのパス
+// - output_data_path: &str -> 出力するデータファイルのパス
+// 戻り値：AppResult<(InputConfigration, ImportedDataMap)> -> テスト構成ファイルの構造体とデータの連想配列
+pub fn import_har(
+    har_path: &str,
+    filter: &HarFilter,
+    output_yml_path: &str,
+    output_data_path: &str,
+) -> AppResult<(InputConfigration, ImportedDataMap)> {
+    println!("[*] Loading HAR file... -> [{}]", har_path);
+    let har: Har = File::open(har_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| Ok(serde_json::from_reader(BufReader::new(file))?))
+        .with_context(|| format!("Failed to load HAR file: {}", har_path))?;
+
+    let mut origins: Vec<String> = Vec::new();
+    let mut init: Vec<InputStep> = Vec::new();
+    let mut login: Option<String> = None;
+    let mut categories: HashMap<String, InputCaterogy> = HashMap::new();
+    let mut data_map = ImportedDataMap::new();
+    // カテゴリ名、メソッド、オリジン、パスの組とステップのref_dataの連想配列
+    let mut step_keys: HashMap<(String, String, String, String), String> = HashMap::new();
+    let mut warnings: Vec<String> = Vec::new();
+    let mut skipped = 0;
+
+    for entry in har.log.entries.iter() {
+        let recorded = match record_entry(entry, filter, &mut warnings) {
+            Some(recorded) => recorded,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        if !origins.contains(&recorded.origin) {
+            origins.push(recorded.origin.clone());
+        }
+        let service = service_name(&origins, &recorded.origin);
+
+        // 最初のログインのリクエストはinitステップにする
+        if login.is_none() && is_login(entry) {
+            let name = step_name(
+                recorded.path.split('?').next().unwrap_or_default(),
+                &recorded.method,
+            );
+            let ref_data = format!("init/{}", name);
+            println!("[*] Detected login request. -> [{}]", name);
+            init.push(InputStep {
+                name: name.clone(),
+                path: recorded.path,
+                method: recorded.method,
+                service,
+                ref_data: ref_data.clone(),
+                option: InputOption {
+                    body: has_body(&recorded.input_data),
+                    query: recorded.input_data.query.is_some(),
+                },
+                ..Default::default()
+            });
+            data_map.insert(ref_data, vec![recorded.input_data]);
+            login = Some(name);
+            continue;
+        }
+
+        // ログインより前のリクエストのカテゴリには、ログイン後のリクエストを含めない
+        let category_name = match login {
+            Some(_)
+                if categories
+                    .get(&recorded.category_name)
+                    .is_some_and(|category| category.login.is_none()) =>
+            {
+                format!("{}_login", recorded.category_name)
+            }
+            _ => recorded.category_name.clone(),
+        };
+        let category = categories.entry(category_name.clone()).or_default();
+        // ログイン後のリクエストのカテゴリは、ログインのクッキーを使用する
+        if login.is_some() {
+            category.login = login.clone();
+        }
+
+        let key = (
+            category_name.clone(),
+            recorded.method.clone(),
+            recorded.origin.clone(),
+            recorded.path.clone(),
+        );
+        match step_keys.get(&key) {
+            Some(ref_data) => {
+                let data = data_map.get_mut(ref_data).unwrap();
+                data.push(recorded.input_data);
+                if let Some(step) = category.steps.iter_mut().find(|s| &s.ref_data == ref_data) {
+                    step.option.body = data.iter().any(has_body);
+                }
+            }
+            None => {
+                let name = unique_step_name(
+                    &category.steps,
+                    step_name(
+                        recorded.path.split('?').next().unwrap_or_default(),
+                        &recorded.method,
+                    ),
+                );
+                let ref_data = format!("{}/{}", category_name, name);
+                category.steps.push(InputStep {
+                    name,
+                    path: recorded.path,
+                    method: recorded.method,
+                    service,
+                    ref_data: ref_data.clone(),
+                    option: InputOption {
+                        body: has_body(&recorded.input_data),
+                        query: recorded.input_data.query.is_some(),
+                    },
+                    ..Default::default()
+                });
+                step_keys.insert(key, ref_data.clone());
+                data_map.insert(ref_data, vec![recorded.input_data]);
+            }
+        }
+    }
+
+    if init.is_empty() && categories.is_empty() {
+        return Err(anyhow!("No requests to import: {}", har_path));
+    }
+    for warning in warnings.iter() {
+        println!("[!] Not translated: {}", warning);
+    }
+    println!(
+        "[*] Imported {} requests into {} steps. ({} skipped)",
+        har.log.entries.len() - skipped,
+        init.len() + categories.values().map(|c| c.steps.len()).sum::<usize>(),
+        skipped
+    );
+
+    let test_config = InputConfigration {
+        base_url: origins.first().cloned().unwrap_or_default(),
+        data: vec![data_source(output_yml_path, output_data_path)?],
+        services: origins
+            .iter()
+            .skip(1)
+            .filter_map(|origin| Some((service_name(&origins, origin)?, origin.clone())))
+            .collect(),
+        init,
+        categories,
+        ..Default::default()
+    };
+
+    Ok((test_config, data_map))
+}
+
+// 記録されたリクエストを変換する関数
+// 絞り込み条件に一致しないリクエスト、完了していないリクエスト(ステータス0)はNoneを返す
+// 引数
+// - entry: &HarEntry -> 記録されたリクエスト。不変参照
+// - filter: &HarFilter -> 絞り込み条件。不変参照
+// - warnings: &mut Vec<String> -> 変換できなかった内容を追加する配列。可変参照
+// 戻り値：Option<Recorded> -> 変換したリクエスト
+fn record_entry(
+    entry: &HarEntry,
+    filter: &HarFilter,
+    warnings: &mut Vec<String>,
+) -> Option<Recorded> {
+    let url = Url::parse(&entry.request.url).ok()?;
+    let host = url.host_str()?;
+    if !filter.hosts.is_empty() && !filter.hosts.iter().any(|h| h == host) {
+        return None;
+    }
+    if let Some(prefix) = filter.path_prefix.as_ref() {
+        if !url.path().starts_with(prefix.as_str()) {
+            return None;
+        }
+    }
+    if entry.response.status == 0 {
+        return None;
+    }
+
+    // IDのセグメントをプレースホルダにし、値をクエリに格納する
+    let mut query: HashMap<String, Value> = HashMap::new();
+    let mut segments: Vec<String> = Vec::new();
+    for segment in url.path().split('/').skip(1) {
+        if id_segment_regex().is_match(segment) {
+            let name = match query.len() {
+                0 => "id".to_string(),
+                n => format!("id{}", n + 1),
+            };
+            segments.push(format!("{{{}}}", name));
+            query.insert(name, path_value(segment));
+        } else {
+            segments.push(segment.to_string());
+        }
+    }
+
+    // クエリ文字列はパスに {name} として追加し、値をクエリに格納する
+    let mut query_params: Vec<String> = Vec::new();
+    for (name, value) in url.query_pairs() {
+        let mut key = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        // IDのプレースホルダと同じ名前の場合は別の名前にする
+        while query.contains_key(&key) {
+            key.push('_');
+        }
+        query_params.push(format!("{}={{{}}}", name, key));
+        query.insert(key, Value::String(value.to_string()));
+    }
+
+    let mut path = format!("/{}", segments.join("/"));
+    if !query_params.is_empty() {
+        path = format!("{}?{}", path, query_params.join("&"));
+    }
+
+    let method = entry.request.method.to_uppercase();
+    let mut input_data = InputData {
+        query: (!query.is_empty()).then_some(query),
+        expect_status: ExpectStatus(vec![StatusPattern::Code(entry.response.status)]),
+        ..Default::default()
+    };
+    if let Some(post_data) = entry.request.post_data.as_ref() {
+        let location = format!("{} {}", method, url.path());
+        convert_post_data(post_data, &mut input_data, &location, warnings);
+    }
+
+    Some(Recorded {
+        origin: url.origin().ascii_serialization(),
+        category_name: category_name(&url),
+        path,
+        method,
+        input_data,
+    })
+}
+
+// 記録されたリクエストボディをデータのボディに変換する関数
+// - jsonオブジェクトはbody、フォームはbody_typeがformのbody、それ以外のテキストはbody_typeがrawのraw_bodyにする
+// - マルチパートはファイルの内容が記録されないため、変換しない
+// 引数
+// - post_data: &HarPostData -> 記録されたリクエストボディ。不変参照
+// - input_data: &mut InputData -> ボディを設定するデータ。可変参照
+// - location: &str -> 警告に使用するリクエストの場所
+// - warnings: &mut Vec<String> -> 変換できなかった内容を追加する配列。可変参照
+fn convert_post_data(
+    post_data: &HarPostData,
+    input_data: &mut InputData,
+    location: &str,
+    warnings: &mut Vec<String>,
+) {
+    let mime_type = post_data
+        .mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let text = post_data.text.as_deref().unwrap_or_default();
+
+    if mime_type == "multipart/form-data" {
+        warnings.push(format!("multipart body -> [{}]", location));
+        return;
+    }
+    if mime_type == "application/x-www-form-urlencoded" {
+        let params: Vec<(String, String)> = if post_data.params.is_empty() {
+            form_params(text)
+        } else {
+            post_data
+                .params
+                .iter()
+                .map(|param| (param.name.clone(), param.value.clone()))
+                .collect()
+        };
+        if !params.is_empty() {
+            input_data.body = Some(
+                params
+                    .into_iter()
+                    .map(|(name, value)| (name, Value::String(value)))
+                    .collect(),
+            );
+            input_data.body_type = Some(BodyType::Form);
+        }
+        return;
+    }
+    if text.is_empty() {
+        return;
+    }
+    if mime_type.contains("json") {
+        if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(text) {
+            input_data.body = Some(map.into_iter().collect());
+            return;
+        }
+    }
+
+    // jsonオブジェクト以外のボディは、記録されたContent-Typeでそのまま送信する
+    input_data.body_type = Some(BodyType::Raw);
+    input_data.raw_body = Some(text.to_string());
+    input_data.content_type =
+        (!post_data.mime_type.is_empty()).then(|| post_data.mime_type.clone());
+}
+
+// application/x-www-form-urlencodedの文字列を名前と値の組に分割する関数
+// 引数：text: &str -> フォームの文字列
+// 戻り値：Vec<(String, String)> -> デコードした名前と値の組の配列
+fn form_params(text: &str) -> Vec<(String, String)> {
+    // クエリ文字列と同じ形式のため、URLのクエリとしてデコードする
+    let mut url = Url::parse("http://localhost/").unwrap();
+    url.set_query(Some(text));
+    url.query_pairs()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+// データが送信するボディを持つかどうかを返す関数
+// 引数：input_data: &InputData -> データ。不変参照
+// 戻り値：bool
+fn has_body(input_data: &InputData) -> bool {
+    input_data.body.is_some() || input_data.raw_body.is_some()
+}
+
+// ログインのリクエストかどうかを返す関数
+// パスにloginなどを含み、Set-Cookieを返したPOSTをログインとみなす
+// 引数：entry: &HarEntry -> 記録されたリクエスト。不変参照
+// 戻り値：bool
+fn is_login(entry: &HarEntry) -> bool {
+    entry.request.method.eq_ignore_ascii_case("POST")
+        && Url::parse(&entry.request.url)
+            .map(|url| login_path_regex().is_match(url.path()))
+            .unwrap_or(false)
+        && entry
+            .response
+            .headers
+            .iter()
+            .any(|header| header.name.eq_ignore_ascii_case("set-cookie"))
+}
+
+// URLからカテゴリ名を返す関数
+// パスの最初のセグメントを使用し、api、v1のような接頭辞とIDは読み飛ばす
+// 引数：url: &Url -> リクエストのURL。不変参照
+// 戻り値：String -> カテゴリ名
+fn category_name(url: &Url) -> String {
+    url.path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .find(|segment| {
+            let is_version = segment
+                .strip_prefix('v')
+                .is_some_and(|version| version.parse::<u32>().is_ok());
+            !(segment.eq_ignore_ascii_case("api")
+                || is_version
+                || id_segment_regex().is_match(segment))
+        })
+        .unwrap_or("root")
+        .to_string()
+}
+
+// オリジンのサービス名を返す関数
+// 最初のオリジンはベースURLとして使用するため、Noneを返す
+// 引数
+// - origins: &[String] -> 記録されたオリジンの配列
+// - origin: &str -> オリジン
+// 戻り値：Option<String> -> サービス名
+fn service_name(origins: &[String], origin: &str) -> Option<String> {
+    if origins.first().map(String::as_str) == Some(origin) {
+        return None;
+    }
+    let host = origin.split("://").nth(1).unwrap_or(origin);
+    Some(
+        host.chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect(),
+    )
+}
+
+// パスのセグメントの値を返す関数
+// 数値の場合は数値、それ以外は文字列にする
+// 引数：segment: &str -> パスのセグメント
+// 戻り値：Value -> 値
+fn path_value(segment: &str) -> Value {
+    segment
+        .parse::<u64>()
+        .map(Value::from)
+        .unwrap_or_else(|_| Value::String(segment.to_string()))
+}
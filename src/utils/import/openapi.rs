@@ -0,0 +1,186 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{data_source, step_name, unique_step_name, ImportedDataMap};
+use crate::utils::openapi::{json_media, OpenApiSpec, Operation};
+use crate::utils::types::{
+    AppResult, ExpectStatus, InputCaterogy, InputConfigration, InputData, InputOption, InputStep,
+};
+use crate::utils::value_to_string;
+
+// 生成したステップを格納するカテゴリ名
+const CATEGORY_NAME: &str = "no_login";
+
+// OpenAPIドキュメントからテスト構成ファイルとデータを生成する関数
+// オペレーションごとにステップと、例の値を使用したデータを一つずつ生成する
+// 引数
+// - spec_path: &str -> OpenAPIドキュメントのパス
+// - server_url: Option<&str> -> ベースURL。指定がない場合はドキュメントの最初のサーバーを使用する
+// - output_yml_path: &str -> 出力するテスト構成ファイルのパス
+// - output_data_path: &str -> 出力するデータファイルのパス
+// 戻り値：AppResult<(InputConfigration, ImportedDataMap)> -> テスト構成ファイルの構造体とデータの連想配列
+pub fn import_openapi(
+    spec_path: &str,
+    server_url: Option<&str>,
+    output_yml_path: &str,
+    output_data_path: &str,
+) -> AppResult<(InputConfigration, ImportedDataMap)> {
+    println!("[*] Loading OpenAPI document... -> [{}]", spec_path);
+    let spec = OpenApiSpec::load(Path::new(spec_path))?;
+
+    let mut steps: Vec<InputStep> = Vec::new();
+    let mut data_map = ImportedDataMap::new();
+
+    for operation in spec.operations()? {
+        let name = unique_step_name(&steps, step_name(operation.path, &operation.method));
+
+        let (path, input_data) = gen_operation_data(&spec, &operation)?;
+        let ref_data = format!("{}/{}", CATEGORY_NAME, name);
+
+        steps.push(InputStep {
+            name,
+            path,
+            method: operation.method.clone(),
+            ref_data: ref_data.clone(),
+            option: InputOption {
+                body: input_data.body.is_some(),
+                query: input_data.query.is_some(),
+            },
+            ..Default::default()
+        });
+        data_map.insert(ref_data, vec![input_data]);
+    }
+
+    println!("[*] Imported {} operations.", steps.len());
+
+    let test_config = InputConfigration {
+        base_url: server_url
+            .or(spec.server_url())
+            .unwrap_or("http://localhost")
+            .trim_end_matches('/')
+            .to_string(),
+        data: vec![data_source(output_yml_path, output_data_path)?],
+        categories: HashMap::from([(
+            CATEGORY_NAME.to_string(),
+            InputCaterogy {
+                steps,
+                ..Default::default()
+            },
+        )]),
+        ..Default::default()
+    };
+
+    Ok((test_config, data_map))
+}
+
+// オペレーションのリクエストのパスとデータを生成する関数
+// - パスパラメータと必須のクエリパラメータはクエリに格納し、クエリパラメータはパスに {name} として追加する
+// - 必須のヘッダパラメータはヘッダに格納する
+// - jsonのリクエストボディの例はボディに格納する
+// - 最初の2xxのレスポンスを予期するステータスコードにする
+// 引数
+// - spec: &OpenApiSpec -> OpenAPIドキュメント。不変参照
+// - operation: &Operation -> オペレーション。不変参照
+// 戻り値：AppResult<(String, InputData)> -> パスとデータのタプルをAppResultでラップしたもの
+fn gen_operation_data(spec: &OpenApiSpec, operation: &Operation) -> AppResult<(String, InputData)> {
+    let mut path = operation.path.to_string();
+    let mut query: HashMap<String, Value> = HashMap::new();
+    let mut headers: HashMap<String, String> = HashMap::new();
+
+    for parameter in operation.parameters_in("path") {
+        if let Some(name) = parameter.get("name").and_then(Value::as_str) {
+            query.insert(name.to_string(), media_example(spec, parameter)?);
+        }
+    }
+
+    let mut query_params: Vec<String> = Vec::new();
+    for parameter in operation.parameters_in("query").filter(|p| is_required(p)) {
+        if let Some(name) = parameter.get("name").and_then(Value::as_str) {
+            query.insert(name.to_string(), media_example(spec, parameter)?);
+            query_params.push(format!("{}={{{}}}", name, name));
+        }
+    }
+    if !query_params.is_empty() {
+        path = format!("{}?{}", path, query_params.join("&"));
+    }
+
+    for parameter in operation.parameters_in("header").filter(|p| is_required(p)) {
+        if let Some(name) = parameter.get("name").and_then(Value::as_str) {
+            headers.insert(
+                name.to_string(),
+                value_to_string(&media_example(spec, parameter)?),
+            );
+        }
+    }
+
+    // jsonのリクエストボディのみを対象とし、オブジェクト以外の例は使用しない
+    let body = match operation.operation.get("requestBody") {
+        Some(request_body) => match json_media(spec.resolve(request_body)?) {
+            Some(media) => match media_example(spec, media)? {
+                Value::Object(body) => Some(body.into_iter().collect()),
+                _ => None,
+            },
+            None => None,
+        },
+        None => None,
+    };
+
+    let input_data = InputData {
+        body,
+        query: (!query.is_empty()).then_some(query),
+        headers: (!headers.is_empty()).then_some(headers),
+        expect_status: expect_status(operation.operation),
+        ..Default::default()
+    };
+
+    Ok((path, input_data))
+}
+
+// オペレーションのレスポンスから予期するステータスコードを決める関数
+// キーの順で最初の2xxのレスポンスを使用し、存在しない場合は200とする
+// 引数：operation: &Value -> オペレーションの定義。不変参照
+// 戻り値：ExpectStatus -> 予期するステータスコード
+fn expect_status(operation: &Value) -> ExpectStatus {
+    operation
+        .get("responses")
+        .and_then(Value::as_object)
+        .and_then(|responses| responses.keys().find(|code| code.starts_with('2')))
+        .and_then(|code| ExpectStatus::try_from(Value::String(code.clone())).ok())
+        .unwrap_or_default()
+}
+
+// メディアタイプまたはパラメータの定義から値の例を返す関数
+// example、examplesの最初の値、スキーマから生成した例の順に使用する
+// 引数
+// - spec: &OpenApiSpec -> OpenAPIドキュメント。不変参照
+// - media: &Value -> example、examples、schemaを持つ定義。不変参照
+// 戻り値：AppResult<Value> -> 値の例をAppResultでラップしたもの
+fn media_example(spec: &OpenApiSpec, media: &Value) -> AppResult<Value> {
+    if let Some(example) = media.get("example") {
+        return Ok(example.clone());
+    }
+    if let Some(example) = media
+        .get("examples")
+        .and_then(Value::as_object)
+        .and_then(|examples| examples.values().next())
+    {
+        if let Some(value) = spec.resolve(example)?.get("value") {
+            return Ok(value.clone());
+        }
+    }
+    match media.get("schema") {
+        Some(schema) => spec.example(schema),
+        None => Ok(Value::Null),
+    }
+}
+
+// パラメータが必須かどうかを返す関数
+// 引数：parameter: &Value -> パラメータの定義。不変参照
+// 戻り値：bool
+fn is_required(parameter: &Value) -> bool {
+    parameter
+        .get("required")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
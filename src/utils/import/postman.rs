@@ -0,0 +1,657 @@
+use anyhow::Context;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::OnceLock;
+
+use super::{data_source, unique_step_name, ImportedDataMap};
+use crate::utils::types::{
+    AppResult, ExpectStatus, InputCaterogy, InputConfigration, InputData, InputOption, InputStep,
+    StatusPattern,
+};
+use crate::utils::value_to_string;
+
+// Postmanのコレクション(v2.1)の構造体を定義する
+#[derive(Debug, Deserialize)]
+struct PostmanCollection {
+    info: PostmanInfo,
+    #[serde(default)]
+    item: Vec<PostmanItem>,
+    #[serde(default)]
+    variable: Vec<PostmanVariable>,
+    #[serde(default)]
+    event: Vec<PostmanEvent>,
+    auth: Option<PostmanAuth>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanInfo {
+    name: String,
+}
+
+// フォルダ(itemを持つ)またはリクエスト(requestを持つ)
+#[derive(Debug, Deserialize)]
+struct PostmanItem {
+    name: String,
+    item: Option<Vec<PostmanItem>>,
+    request: Option<PostmanRequest>,
+    #[serde(default)]
+    response: Vec<PostmanResponse>,
+    #[serde(default)]
+    event: Vec<PostmanEvent>,
+    auth: Option<PostmanAuth>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanRequest {
+    method: Option<String>,
+    #[serde(default)]
+    header: Vec<PostmanHeader>,
+    url: Option<PostmanUrl>,
+    body: Option<PostmanBody>,
+    auth: Option<PostmanAuth>,
+}
+
+// URLは文字列、または分解されたオブジェクトで記述される
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PostmanUrl {
+    Raw(String),
+    Detail {
+        raw: Option<String>,
+        #[serde(default)]
+        variable: Vec<PostmanVariable>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanHeader {
+    key: String,
+    value: String,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanBody {
+    mode: String,
+    raw: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanResponse {
+    name: Option<String>,
+    #[serde(rename = "originalRequest")]
+    original_request: Option<PostmanRequest>,
+    code: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanEvent {
+    listen: String,
+    script: Option<PostmanScript>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanScript {
+    exec: Option<Value>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct PostmanAuth {
+    #[serde(rename = "type")]
+    auth_type: String,
+    #[serde(default)]
+    bearer: Vec<PostmanVariable>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct PostmanVariable {
+    key: String,
+    value: Option<Value>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+// Postmanの環境ファイルの構造体を定義する
+#[derive(Debug, Deserialize)]
+struct PostmanEnvironment {
+    #[serde(default)]
+    values: Vec<PostmanEnvironmentValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanEnvironmentValue {
+    key: String,
+    value: Option<Value>,
+    #[serde(default = "enabled_default")]
+    enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+// {{ name }} にマッチする正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn variable_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*([^{}]+?)\s*\}\}").unwrap())
+}
+
+// パス変数 :name にマッチする正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn path_variable_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"/:(\w+)").unwrap())
+}
+
+// URLのオリジン(scheme://host:port)とそれ以降に分割する正規表現を取得する関数
+// 戻り値：&'static Regex -> コンパイル済みの正規表現
+fn origin_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([a-zA-Z][a-zA-Z0-9+.-]*://[^/?#]+)(.*)$").unwrap())
+}
+
+// 変数を置換する場所を定義する
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    // パス。変数はプレースホルダ {name} にする
+    Path,
+    // ヘッダ。変数はプレースホルダ {name}、動的な変数は {{ expr }} にする
+    Header,
+    // ボディ。変数は値、動的な変数は {{ expr }} にする
+    Body,
+}
+
+// コレクションの変換の状態を格納する構造体
+struct Converter {
+    // raxtestの変数名と値の連想配列
+    variables: HashMap<String, String>,
+    // オリジンとサービス名の組。最初のオリジンはベースURLにする
+    origins: Vec<(String, String)>,
+    // 変換できなかった内容
+    warnings: Vec<String>,
+    steps: HashMap<String, Vec<InputStep>>,
+    category_names: Vec<String>,
+    data_map: ImportedDataMap,
+}
+
+// Postmanのコレクションからテスト構成ファイルとデータを生成する関数
+// - フォルダはカテゴリ、リクエストはステップにする。ネストしたフォルダは / で連結した名前にする
+// - コレクションと環境の変数はテスト構成ファイルの変数にする
+// - 保存されたレスポンスの例はそれぞれデータにし、例がない場合はリクエストからデータを一つ生成する
+// - pre-requestスクリプトやテストなど、変換できない内容は警告として表示する
+// 引数
+// - collection_path: &str -> コレクションのパス
+// - environment_path: Option<&str> -> 環境ファイルのパス
+// - output_yml_path: &str -> 出力するテスト構成ファイルのパス
+// - output_data_path: &str -> 出力するデータファイルのパス
+// 戻り値：AppResult<(InputConfigration, ImportedDataMap)> -> テスト構成ファイルの構造体とデータの連想配列
+pub fn import_postman(
+    collection_path: &str,
+    environment_path: Option<&str>,
+    output_yml_path: &str,
+    output_data_path: &str,
+) -> AppResult<(InputConfigration, ImportedDataMap)> {
+    println!("[*] Loading Postman collection... -> [{}]", collection_path);
+    let collection: PostmanCollection = read_json(collection_path)
+        .with_context(|| format!("Failed to load Postman collection: {}", collection_path))?;
+
+    let mut converter = Converter {
+        variables: HashMap::new(),
+        origins: Vec::new(),
+        warnings: Vec::new(),
+        steps: HashMap::new(),
+        category_names: Vec::new(),
+        data_map: ImportedDataMap::new(),
+    };
+
+    // コレクションの変数を環境の変数で上書きする
+    for variable in collection.variable.iter().filter(|v| !v.disabled) {
+        converter.insert_variable(&variable.key, variable.value.as_ref());
+    }
+    if let Some(environment_path) = environment_path {
+        println!(
+            "[*] Loading Postman environment... -> [{}]",
+            environment_path
+        );
+        let environment: PostmanEnvironment = read_json(environment_path)
+            .with_context(|| format!("Failed to load Postman environment: {}", environment_path))?;
+        for value in environment.values.iter().filter(|v| v.enabled) {
+            converter.insert_variable(&value.key, value.value.as_ref());
+        }
+    }
+
+    converter.check_events(&collection.event, &collection.info.name);
+    converter.convert_items(
+        &collection.item,
+        &collection.info.name,
+        None,
+        collection.auth.as_ref(),
+    );
+
+    for warning in converter.warnings.iter() {
+        println!("[!] Not translated: {}", warning);
+    }
+    println!(
+        "[*] Imported {} requests. ({} warnings)",
+        converter.steps.values().map(Vec::len).sum::<usize>(),
+        converter.warnings.len()
+    );
+
+    let mut origins = converter.origins.into_iter();
+    let base_url = origins
+        .next()
+        .map(|(origin, _)| origin)
+        .unwrap_or_else(|| "http://localhost".to_string());
+
+    let mut steps = converter.steps;
+    let test_config = InputConfigration {
+        base_url,
+        data: vec![data_source(output_yml_path, output_data_path)?],
+        services: origins.map(|(origin, name)| (name, origin)).collect(),
+        variables: converter.variables,
+        categories: converter
+            .category_names
+            .iter()
+            .map(|name| {
+                (
+                    name.clone(),
+                    InputCaterogy {
+                        steps: steps.remove(name).unwrap_or_default(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    Ok((test_config, converter.data_map))
+}
+
+// jsonファイルを読み込む関数
+// 引数：path: &str -> jsonファイルのパス
+// 戻り値：AppResult<T> -> 読み込んだ値をAppResultでラップしたもの
+fn read_json<T: for<'de> Deserialize<'de>>(path: &str) -> AppResult<T> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+impl Converter {
+    // 変数を追加する関数
+    // raxtestのプレースホルダで使用できるように、変数名の英数字とアンダースコア以外の文字はアンダースコアにする
+    // 引数
+    // - key: &str -> Postmanの変数名
+    // - value: Option<&Value> -> 変数の値
+    fn insert_variable(&mut self, key: &str, value: Option<&Value>) {
+        self.variables.insert(
+            variable_name(key),
+            value.map(value_to_string).unwrap_or_default(),
+        );
+    }
+
+    // フォルダとリクエストを再帰的に変換する関数
+    // 引数
+    // - items: &[PostmanItem] -> フォルダまたはリクエストの配列。不変参照
+    // - category_name: &str -> リクエストを追加するカテゴリ名
+    // - folder: Option<&str> -> 親フォルダのカテゴリ名。トップレベルの場合はNone
+    // - auth: Option<&PostmanAuth> -> 親から継承する認証
+    fn convert_items(
+        &mut self,
+        items: &[PostmanItem],
+        category_name: &str,
+        folder: Option<&str>,
+        auth: Option<&PostmanAuth>,
+    ) {
+        for item in items {
+            let auth = item.auth.as_ref().or(auth);
+            match (&item.item, &item.request) {
+                (Some(children), _) => {
+                    let child_category = match folder {
+                        Some(folder) => format!("{}/{}", folder, item.name),
+                        None => item.name.clone(),
+                    };
+                    self.check_events(&item.event, &child_category);
+                    self.convert_items(children, &child_category, Some(&child_category), auth);
+                }
+                (None, Some(request)) => {
+                    self.convert_request(item, request, category_name, auth);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    // リクエストをステップとデータに変換する関数
+    // 引数
+    // - item: &PostmanItem -> リクエストのアイテム。不変参照
+    // - request: &PostmanRequest -> リクエスト。不変参照
+    // - category_name: &str -> ステップを追加するカテゴリ名
+    // - auth: Option<&PostmanAuth> -> 親から継承した認証
+    fn convert_request(
+        &mut self,
+        item: &PostmanItem,
+        request: &PostmanRequest,
+        category_name: &str,
+        auth: Option<&PostmanAuth>,
+    ) {
+        let location = format!("{}/{}", category_name, item.name);
+        self.check_events(&item.event, &location);
+
+        if !self.category_names.iter().any(|name| name == category_name) {
+            self.category_names.push(category_name.to_string());
+        }
+        let name = unique_step_name(
+            self.steps
+                .get(category_name)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            item.name.clone(),
+        );
+
+        // URLをオリジンとパスに分割する
+        let (raw_url, path_variables) = match &request.url {
+            Some(PostmanUrl::Raw(raw)) => (raw.clone(), Vec::new()),
+            Some(PostmanUrl::Detail { raw, variable }) => {
+                (raw.clone().unwrap_or_default(), variable.clone())
+            }
+            None => (String::new(), Vec::new()),
+        };
+        let (service, path) = self.split_origin(&raw_url, &location);
+        let path = path_variable_regex()
+            .replace_all(&path, "/{$1}")
+            .to_string();
+        let path = self.convert_text(&path, Target::Path, &location);
+
+        // ヘッダと認証を変換する
+        let mut headers: HashMap<String, String> = HashMap::new();
+        for header in request.header.iter().filter(|h| !h.disabled) {
+            headers.insert(
+                header.key.clone(),
+                self.convert_text(&header.value, Target::Header, &location),
+            );
+        }
+        match request.auth.as_ref().or(auth) {
+            Some(auth) if auth.auth_type == "bearer" => {
+                let token = auth
+                    .bearer
+                    .iter()
+                    .find(|v| v.key == "token")
+                    .and_then(|v| v.value.as_ref())
+                    .map(value_to_string)
+                    .unwrap_or_default();
+                let token = self.convert_text(&token, Target::Header, &location);
+                headers.insert("Authorization".to_string(), format!("Bearer {}", token));
+            }
+            Some(auth) if auth.auth_type != "noauth" => {
+                self.warnings
+                    .push(format!("{} auth -> [{}]", auth.auth_type, location));
+            }
+            _ => {}
+        }
+
+        // パス変数の値はクエリに格納する
+        let query: HashMap<String, Value> = path_variables
+            .iter()
+            .map(|v| {
+                (
+                    variable_name(&v.key),
+                    v.value.clone().unwrap_or(Value::String(String::new())),
+                )
+            })
+            .collect();
+
+        // 保存されたレスポンスの例をデータにする。例がない場合はリクエストからデータを一つ生成する
+        let mut data: Vec<InputData> = Vec::new();
+        for response in item.response.iter() {
+            let example_location = format!(
+                "{} ({})",
+                location,
+                response.name.as_deref().unwrap_or("example")
+            );
+            let example_request = response.original_request.as_ref().unwrap_or(request);
+            let body = self.convert_body(example_request.body.as_ref(), &example_location);
+            data.push(InputData {
+                body,
+                query: (!query.is_empty()).then(|| query.clone()),
+                expect_status: ExpectStatus(vec![StatusPattern::Code(
+                    response.code.unwrap_or(200),
+                )]),
+                ..Default::default()
+            });
+        }
+        if data.is_empty() {
+            data.push(InputData {
+                body: self.convert_body(request.body.as_ref(), &location),
+                query: (!query.is_empty()).then(|| query.clone()),
+                ..Default::default()
+            });
+        }
+
+        let ref_data = format!("{}/{}", category_name, name);
+        let step = InputStep {
+            name,
+            path,
+            method: request
+                .method
+                .clone()
+                .unwrap_or_else(|| "GET".to_string())
+                .to_uppercase(),
+            service,
+            ref_data: ref_data.clone(),
+            headers,
+            option: InputOption {
+                body: data.iter().any(|d| d.body.is_some()),
+                query: !query.is_empty(),
+            },
+            ..Default::default()
+        };
+        self.steps
+            .entry(category_name.to_string())
+            .or_default()
+            .push(step);
+        self.data_map.insert(ref_data, data);
+    }
+
+    // URLをオリジンとパスに分割する関数
+    // URLが変数から始まる場合は変数の値をオリジンとして使用する
+    // 最初に現れたオリジンはベースURL、それ以外はサービスにする
+    // 引数
+    // - raw_url: &str -> PostmanのURL
+    // - location: &str -> 警告に使用するリクエストの場所
+    // 戻り値：(Option<String>, String) -> サービス名(ベースURLの場合はNone)とパス
+    fn split_origin(&mut self, raw_url: &str, location: &str) -> (Option<String>, String) {
+        // 先頭の変数を値に置換し、変数名をサービス名の候補にする
+        let mut url = raw_url.to_string();
+        let mut service_name = None;
+        if let Some(captures) = variable_regex().captures(raw_url) {
+            let matched = captures.get(0).unwrap();
+            let name = variable_name(&captures[1]);
+            if matched.start() == 0 {
+                if let Some(value) = self.variables.get(&name) {
+                    url = format!("{}{}", value, &raw_url[matched.end()..]);
+                    service_name = Some(name);
+                }
+            }
+        }
+
+        // オリジンのパス部分(例: https://example.com/v1 の /v1)はパスに含める
+        let (origin, path) = match origin_regex().captures(&url) {
+            Some(captures) => (captures[1].to_string(), captures[2].to_string()),
+            None => {
+                self.warnings
+                    .push(format!("url without host: {} -> [{}]", raw_url, location));
+                return (None, url);
+            }
+        };
+        let path = if path.starts_with('/') {
+            path
+        } else {
+            format!("/{}", path)
+        };
+
+        if let Some(index) = self.origins.iter().position(|(o, _)| *o == origin) {
+            let service = (index > 0).then(|| self.origins[index].1.clone());
+            return (service, path);
+        }
+        let name = service_name.unwrap_or_else(|| variable_name(&origin_host(&origin)));
+        self.origins.push((origin, name.clone()));
+        ((self.origins.len() > 1).then_some(name), path)
+    }
+
+    // リクエストボディをデータのボディに変換する関数
+    // jsonオブジェクトのrawボディのみに対応する
+    // 引数
+    // - body: Option<&PostmanBody> -> リクエストボディ
+    // - location: &str -> 警告に使用するリクエストの場所
+    // 戻り値：Option<HashMap<String, Value>> -> データのボディ
+    fn convert_body(
+        &mut self,
+        body: Option<&PostmanBody>,
+        location: &str,
+    ) -> Option<HashMap<String, Value>> {
+        let body = body?;
+        let raw = match (body.mode.as_str(), body.raw.as_deref()) {
+            ("raw", Some(raw)) if raw.trim().is_empty() => return None,
+            ("raw", Some(raw)) => raw,
+            ("raw", None) => return None,
+            (mode, _) => {
+                self.warnings
+                    .push(format!("{} body -> [{}]", mode, location));
+                return None;
+            }
+        };
+
+        // 変数を含むjsonはそのままではパースできないため、先に値を置換する
+        let raw = self.convert_text(raw, Target::Body, location);
+        match serde_json::from_str::<Value>(&raw) {
+            Ok(Value::Object(map)) => Some(map.into_iter().collect()),
+            _ => {
+                self.warnings
+                    .push(format!("non-json object raw body -> [{}]", location));
+                None
+            }
+        }
+    }
+
+    // 文字列の {{ name }} を置換する関数
+    // - パスとヘッダの変数はプレースホルダ {name}、ボディの変数は値にする
+    // - ヘッダとボディの動的な変数({{$guid}}など)はデータ生成の関数にする
+    // 引数
+    // - text: &str -> 置換する文字列
+    // - target: Target -> 置換する場所
+    // - location: &str -> 警告に使用するリクエストの場所
+    // 戻り値：String -> 置換後の文字列
+    fn convert_text(&mut self, text: &str, target: Target, location: &str) -> String {
+        let mut warnings = Vec::new();
+        let converted = variable_regex().replace_all(text, |captures: &Captures| {
+            let name = &captures[1];
+            if let Some(dynamic) = name.strip_prefix('$') {
+                match (dynamic_variable(dynamic), target) {
+                    (Some(expression), Target::Header | Target::Body) => {
+                        format!("{{{{{}}}}}", expression)
+                    }
+                    _ => {
+                        warnings.push(format!(
+                            "dynamic variable {{{{{}}}}} -> [{}]",
+                            name, location
+                        ));
+                        String::new()
+                    }
+                }
+            } else {
+                let name = variable_name(name);
+                match target {
+                    Target::Path | Target::Header => format!("{{{}}}", name),
+                    Target::Body => match self.variables.get(&name) {
+                        Some(value) => value.clone(),
+                        None => {
+                            warnings.push(format!(
+                                "undefined variable {{{{{}}}}} -> [{}]",
+                                name, location
+                            ));
+                            format!("{{{}}}", name)
+                        }
+                    },
+                }
+            }
+        });
+        let converted = converted.to_string();
+        self.warnings.extend(warnings);
+        converted
+    }
+
+    // pre-requestスクリプトとテストを警告に追加する関数
+    // 引数
+    // - events: &[PostmanEvent] -> イベントの配列
+    // - location: &str -> 警告に使用する場所
+    fn check_events(&mut self, events: &[PostmanEvent], location: &str) {
+        for event in events {
+            let has_script = match event.script.as_ref().and_then(|s| s.exec.as_ref()) {
+                Some(Value::Array(lines)) => lines
+                    .iter()
+                    .any(|line| line.as_str().is_some_and(|l| !l.trim().is_empty())),
+                Some(Value::String(script)) => !script.trim().is_empty(),
+                _ => false,
+            };
+            if has_script {
+                let kind = match event.listen.as_str() {
+                    "prerequest" => "pre-request script",
+                    "test" => "test script",
+                    _ => "script",
+                };
+                self.warnings.push(format!("{} -> [{}]", kind, location));
+            }
+        }
+    }
+}
+
+// Postmanの動的な変数に対応するデータ生成の式を返す関数
+// 引数：name: &str -> $を除いた変数名
+// 戻り値：Option<&str> -> データ生成の式。対応しない場合はNone
+fn dynamic_variable(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "guid" | "randomUUID" => "uuid",
+        "timestamp" => "now | unix",
+        "isoTimestamp" => "now",
+        "randomInt" => "random.int(0, 1000)",
+        "randomFirstName" => "faker.first_name",
+        "randomLastName" => "faker.last_name",
+        "randomFullName" => "faker.name",
+        "randomUserName" => "faker.username",
+        "randomEmail" | "randomExampleEmail" => "faker.email",
+        "randomWord" => "faker.word",
+        "randomAlphaNumeric" => "random.string(1)",
+        _ => return None,
+    })
+}
+
+// Postmanの変数名をraxtestの変数名に変換する関数
+// 英数字とアンダースコア以外の文字はアンダースコアにする
+// 引数：key: &str -> Postmanの変数名
+// 戻り値：String -> raxtestの変数名
+fn variable_name(key: &str) -> String {
+    key.trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// オリジンからホスト名を返す関数
+// 引数：origin: &str -> オリジン(scheme://host:port)
+// 戻り値：String -> ホスト名
+fn origin_host(origin: &str) -> String {
+    let host = origin.split("://").nth(1).unwrap_or(origin);
+    host.split(':').next().unwrap_or(host).to_string()
+}
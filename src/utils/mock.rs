@@ -0,0 +1,253 @@
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use super::types::{AppResult, ExpectStatus, InputConfigration, InputDataMap, StatusPattern};
+use super::{
+    base_path, gen_flatten_step, gen_variables, interpolate, placeholder_regex, resolve_base_url,
+    sorted_categories, sorted_object,
+};
+
+// initステップのルートが返すクッキーの名前
+const MOCK_COOKIE: &str = "raxtest_mock";
+
+// モックサーバーのルートを格納する構造体
+// フラットされたステップ(データのケース)ごとに一つ生成する
+struct MockRoute {
+    // ステップ名(カテゴリ名/ステップ名)
+    name: String,
+    method: String,
+    // ベースURLのパスを含む、置換前のパス(例: /api/users/{id})
+    template: String,
+    // 置換前のパスにマッチする正規表現
+    pattern: Regex,
+    // データのクエリで置換したパス。クエリ文字列を含む
+    path: String,
+    // データのリクエストボディ
+    body: Option<Value>,
+    // 返すステータスコード
+    status: u16,
+    // 返すレスポンスボディ
+    response: Option<Value>,
+    // initステップの場合は、ログインのクッキーを返す
+    set_cookie: Option<String>,
+}
+
+// テスト構成ファイルのステップをモックサーバーとして提供する関数
+// Ctrl+Cで停止するまでリクエストを受け付ける
+// 引数
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// - input_data_map: &InputDataMap -> jsonデータの連想配列。不変参照
+// - addr: SocketAddr -> 待ち受けるアドレス
+// 戻り値：AppResult<()>
+pub async fn run_mock(
+    test_config: &InputConfigration,
+    input_data_map: &InputDataMap,
+    addr: SocketAddr,
+) -> AppResult<()> {
+    let routes = Arc::new(gen_routes(test_config, input_data_map)?);
+    for route in routes.iter() {
+        println!(
+            "[*] Route: {} {} ({}) -> [{}]",
+            route.method, route.template, route.status, route.name
+        );
+    }
+
+    let make_service = make_service_fn(move |_| {
+        let routes = routes.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(routes.clone(), request)
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    println!("[*] Mock server is listening... -> [http://{}]", addr);
+    server
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await?;
+    println!("[*] Mock server stopped.");
+
+    Ok(())
+}
+
+// initステップと全てのカテゴリのステップからルートを生成する関数
+// 引数
+// - test_config: &InputConfigration -> テスト構成ファイルの構造体。不変参照
+// - input_data_map: &InputDataMap -> jsonデータの連想配列。不変参照
+// 戻り値：AppResult<Vec<MockRoute>> -> ルートの配列をAppResultでラップしたもの
+fn gen_routes(
+    test_config: &InputConfigration,
+    input_data_map: &InputDataMap,
+) -> AppResult<Vec<MockRoute>> {
+    let mut routes = Vec::new();
+
+    for init_step in test_config.init.iter() {
+        let base_path = base_path(resolve_base_url(test_config, init_step.service.as_ref())?);
+        let data = input_data_map
+            .get(&init_step.ref_data)
+            .and_then(|data| data.first());
+        // テストの実行と同じく、オプションでtrueが指定された場合はデータのクエリの値でパスを置換する
+        let variables = gen_variables(
+            &test_config.variables,
+            data.filter(|_| init_step.option.query)
+                .and_then(|data| data.query.as_ref()),
+        );
+        let template = format!("{}{}", base_path, init_step.path);
+        routes.push(MockRoute {
+            name: format!("init/{}", init_step.name),
+            method: init_step.method.to_uppercase(),
+            pattern: path_pattern(&template)?,
+            template,
+            path: format!("{}{}", base_path, interpolate(&init_step.path, &variables)),
+            body: data.and_then(|data| data.body.as_ref()).map(sorted_object),
+            status: data
+                .map(|data| mock_status(&data.expect_status))
+                .unwrap_or(200),
+            response: data.and_then(|data| data.mock_response.clone()),
+            set_cookie: Some(format!("{}={}", MOCK_COOKIE, init_step.name)),
+        });
+    }
+
+    for (category_name, category) in sorted_categories(test_config) {
+        // WebSocketのステップは、モックサーバーのルートに含めない
+        for (_, _, test_step) in gen_flatten_step(test_config, category, input_data_map)?
+            .into_iter()
+            .filter(|(_, _, test_step)| test_step.websocket.is_none())
+        {
+            let base_path = base_path(&test_step.base_url);
+            let template = format!("{}{}", base_path, test_step.path);
+            routes.push(MockRoute {
+                name: format!("{}/{}", category_name, test_step.name),
+                method: test_step.method.to_uppercase(),
+                pattern: path_pattern(&template)?,
+                template,
+                path: format!(
+                    "{}{}",
+                    base_path,
+                    interpolate(&test_step.path, &test_step.variables)
+                ),
+                // GraphQLのクエリなど、生成したjsonのボディと比較する
+                body: test_step
+                    .request_body
+                    .as_ref()
+                    .and_then(|body| body.as_json())
+                    .cloned(),
+                status: mock_status(&test_step.input_data.expect_status),
+                response: test_step.input_data.mock_response,
+                set_cookie: None,
+            });
+        }
+    }
+
+    Ok(routes)
+}
+
+// リクエストにマッチするルートのレスポンスを返す関数
+// パスのパターンとメソッドが一致するルートのうち、
+// 置換済みのパスとリクエストボディがより多く一致するものを優先する
+// 引数
+// - routes: Arc<Vec<MockRoute>> -> ルートの配列
+// - request: Request<Body> -> 受信したリクエスト
+// 戻り値：Result<Response<Body>, Infallible> -> レスポンス
+async fn handle_request(
+    routes: Arc<Vec<MockRoute>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str().to_string())
+        .unwrap_or_else(|| path.clone());
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok());
+
+    // max_by_keyは同点の場合に後ろの要素を返すため、逆順にして先に定義されたルートを優先する
+    let route = routes
+        .iter()
+        .rev()
+        .filter(|route| route.method == method && route.pattern.is_match(&path))
+        .max_by_key(|route| {
+            let mut score = 0;
+            if route.path == path_and_query {
+                score += 4;
+            } else if route.path.split('?').next() == Some(path.as_str()) {
+                score += 2;
+            }
+            if route.body.is_some() && route.body == body {
+                score += 1;
+            }
+            score
+        });
+
+    let response = match route {
+        Some(route) => {
+            println!(
+                "[*] Request: {} {} ({}) -> [{}]",
+                method, path_and_query, route.status, route.name
+            );
+            let mut builder = Response::builder().status(route.status);
+            if let Some(cookie) = &route.set_cookie {
+                builder = builder.header("Set-Cookie", cookie);
+            }
+            match &route.response {
+                Some(response) => builder
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(response.to_string())),
+                None => builder.body(Body::empty()),
+            }
+        }
+        None => {
+            println!("[!] No route matched. -> [{} {}]", method, path_and_query);
+            Response::builder()
+                .status(404)
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    json!({ "error": format!("no mock route: {} {}", method, path) }).to_string(),
+                ))
+        }
+    };
+
+    Ok(response.unwrap_or_else(|_| Response::new(Body::empty())))
+}
+
+// 置換前のパスにマッチする正規表現を生成する関数
+// プレースホルダ {key} は一つのセグメントにマッチさせ、クエリ文字列は無視する
+// 引数：template: &str -> 置換前のパス
+// 戻り値：AppResult<Regex> -> 正規表現をAppResultでラップしたもの
+fn path_pattern(template: &str) -> AppResult<Regex> {
+    let template = template.split('?').next().unwrap_or_default();
+    let mut pattern = String::from("^");
+    let mut last = 0;
+    for placeholder in placeholder_regex().find_iter(template) {
+        pattern.push_str(&regex::escape(&template[last..placeholder.start()]));
+        pattern.push_str("[^/]+");
+        last = placeholder.end();
+    }
+    pattern.push_str(&regex::escape(&template[last..]));
+    pattern.push('$');
+    Ok(Regex::new(&pattern)?)
+}
+
+// 予期するステータスコードから、モックサーバーが返すステータスコードを決める関数
+// 最初のパターンを使用し、クラスの場合はその最初のコード(例: 4xx -> 400)を返す
+// 引数：expect_status: &ExpectStatus -> 予期するステータスコード。不変参照
+// 戻り値：u16 -> ステータスコード
+fn mock_status(expect_status: &ExpectStatus) -> u16 {
+    match expect_status.0.first() {
+        Some(StatusPattern::Code(code)) => *code,
+        Some(StatusPattern::Class(class)) => *class * 100,
+        None => 200,
+    }
+}
@@ -0,0 +1,311 @@
+use anyhow::{anyhow, Context};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::types::AppResult;
+
+// パスアイテムに記述できるHTTPメソッド
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+// $refやスキーマの例を辿る深さの上限
+// 再帰的なスキーマで無限に辿らないために使用する
+const MAX_DEPTH: usize = 16;
+
+// OpenAPIドキュメントを格納する構造体
+pub struct OpenApiSpec {
+    root: Value,
+}
+
+// OpenAPIドキュメントのオペレーションを格納する構造体
+pub struct Operation<'a> {
+    // ドキュメントに記述されたパス(例: /api/users/{id})
+    pub path: &'a str,
+    // 大文字のHTTPメソッド
+    pub method: String,
+    // オペレーションの定義
+    pub operation: &'a Value,
+    // パスアイテムとオペレーションのパラメータをマージしたもの
+    pub parameters: Vec<&'a Value>,
+}
+
+impl OpenApiSpec {
+    // OpenAPIドキュメントを読み込む関数
+    // yamlとjsonのどちらの形式でも読み込める
+    // 引数：path: &Path -> OpenAPIドキュメントのパス。不変参照
+    // 戻り値：AppResult<OpenApiSpec> -> 読み込んだドキュメントをAppResultでラップしたもの
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open OpenAPI document: {}", path.display()))?;
+        let root: Value = serde_yaml::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to parse OpenAPI document: {}", path.display()))?;
+
+        match root.get("openapi").and_then(Value::as_str) {
+            Some(version) if version.starts_with("3.") => Ok(OpenApiSpec { root }),
+            Some(version) => Err(anyhow!("Unsupported OpenAPI version: {}", version)),
+            None => Err(anyhow!(
+                "Unsupported OpenAPI document: {} (only OpenAPI 3.x is supported)",
+                path.display()
+            )),
+        }
+    }
+
+    // ドキュメントに記述された最初のサーバーのURLを返す関数
+    // 戻り値：Option<&str> -> サーバーのURL。記述がない場合はNone
+    pub fn server_url(&self) -> Option<&str> {
+        self.root.pointer("/servers/0/url").and_then(Value::as_str)
+    }
+
+    // 全てのオペレーションを返す関数
+    // パスの順、パスアイテム内ではMETHODSの順に並べる
+    // 戻り値：AppResult<Vec<Operation>> -> オペレーションの配列をAppResultでラップしたもの
+    pub fn operations(&self) -> AppResult<Vec<Operation<'_>>> {
+        let mut operations = Vec::new();
+        let paths = match self.root.get("paths").and_then(Value::as_object) {
+            Some(paths) => paths,
+            None => return Ok(operations),
+        };
+
+        for (path, path_item) in paths {
+            let path_item = self.resolve(path_item)?;
+            let common_parameters = self.parameters(path_item)?;
+
+            for method in METHODS {
+                let operation = match path_item.get(method) {
+                    Some(operation) => operation,
+                    None => continue,
+                };
+
+                // 同じ名前と場所のパラメータはオペレーションの定義を優先する
+                let mut parameters = self.parameters(operation)?;
+                for common in common_parameters.iter() {
+                    if !parameters.iter().any(|p| {
+                        p.get("name") == common.get("name") && p.get("in") == common.get("in")
+                    }) {
+                        parameters.push(common);
+                    }
+                }
+
+                operations.push(Operation {
+                    path,
+                    method: method.to_uppercase(),
+                    operation,
+                    parameters,
+                });
+            }
+        }
+
+        Ok(operations)
+    }
+
+    // $refを辿り、参照先の値を返す関数
+    // ドキュメント内の参照(#/...)のみに対応する
+    // 引数：value: &Value -> $refを含む可能性がある値。不変参照
+    // 戻り値：AppResult<&Value> -> 参照先の値をAppResultでラップしたもの
+    pub fn resolve<'a>(&'a self, mut value: &'a Value) -> AppResult<&'a Value> {
+        for _ in 0..MAX_DEPTH {
+            let reference = match value.get("$ref").and_then(Value::as_str) {
+                Some(reference) => reference,
+                None => return Ok(value),
+            };
+            value = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+                .ok_or_else(|| anyhow!("Unresolved $ref: {}", reference))?;
+        }
+        Err(anyhow!("Too deep $ref chain"))
+    }
+
+    // ドキュメントのOpenAPIのバージョンを返す関数
+    // 戻り値：&str -> バージョン(例: 3.0.3)
+    pub fn version(&self) -> &str {
+        self.root
+            .get("openapi")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+
+    // スキーマの$refを全て展開し、JSON Schemaとして検証できる値を返す関数
+    // 3.0のnullable: trueは型にnullを追加する
+    // 再帰的なスキーマは、展開中の$refに戻った時点で任意の値を受け付けるスキーマとする
+    // 引数：schema: &Value -> スキーマ。不変参照
+    // 戻り値：AppResult<Value> -> 展開したスキーマをAppResultでラップしたもの
+    pub fn inline_schema(&self, schema: &Value) -> AppResult<Value> {
+        self.inline_schema_at(schema, &mut Vec::new())
+    }
+
+    fn inline_schema_at<'a>(
+        &'a self,
+        schema: &'a Value,
+        refs: &mut Vec<&'a str>,
+    ) -> AppResult<Value> {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            if refs.contains(&reference) || refs.len() > MAX_DEPTH {
+                return Ok(Value::Object(Map::new()));
+            }
+            refs.push(reference);
+            let inlined = self.inline_schema_at(self.resolve(schema)?, refs);
+            refs.pop();
+            return inlined;
+        }
+
+        Ok(match schema {
+            Value::Object(map) => {
+                let mut inlined = Map::new();
+                for (key, value) in map {
+                    inlined.insert(key.clone(), self.inline_schema_at(value, refs)?);
+                }
+                if inlined.remove("nullable") == Some(Value::Bool(true)) {
+                    if let Some(Value::String(schema_type)) = inlined.get("type") {
+                        let types = vec![Value::String(schema_type.clone()), Value::from("null")];
+                        inlined.insert("type".to_string(), Value::Array(types));
+                    }
+                }
+                Value::Object(inlined)
+            }
+            Value::Array(values) => Value::Array(
+                values
+                    .iter()
+                    .map(|value| self.inline_schema_at(value, refs))
+                    .collect::<AppResult<Vec<Value>>>()?,
+            ),
+            value => value.clone(),
+        })
+    }
+
+    // スキーマから値の例を生成する関数
+    // example、default、enumの最初の値、型に応じた値の順に使用する
+    // 再帰的なスキーマは、展開中の$refに戻った時点でnullとし、必須でないプロパティは省略する
+    // 引数：schema: &Value -> スキーマ。不変参照
+    // 戻り値：AppResult<Value> -> 値の例をAppResultでラップしたもの
+    pub fn example(&self, schema: &Value) -> AppResult<Value> {
+        self.example_at(schema, &mut Vec::new())
+    }
+
+    fn example_at<'a>(&'a self, schema: &'a Value, refs: &mut Vec<&'a str>) -> AppResult<Value> {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            if refs.contains(&reference) || refs.len() > MAX_DEPTH {
+                return Ok(Value::Null);
+            }
+            refs.push(reference);
+            let example = self.example_at(self.resolve(schema)?, refs);
+            refs.pop();
+            return example;
+        }
+
+        if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+            return Ok(example.clone());
+        }
+        if let Some(first) = schema.get("enum").and_then(|e| e.get(0)) {
+            return Ok(first.clone());
+        }
+
+        // allOfは全てのスキーマの例をマージし、oneOfとanyOfは最初のスキーマを使用する
+        if let Some(schemas) = schema.get("allOf").and_then(Value::as_array) {
+            let mut merged = Map::new();
+            for schema in schemas {
+                match self.example_at(schema, refs)? {
+                    Value::Object(map) => merged.extend(map),
+                    value => return Ok(value),
+                }
+            }
+            return Ok(Value::Object(merged));
+        }
+        if let Some(first) = ["oneOf", "anyOf"]
+            .iter()
+            .find_map(|key| schema.get(*key).and_then(|s| s.get(0)))
+        {
+            return self.example_at(first, refs);
+        }
+
+        let schema_type = match schema.get("type") {
+            Some(Value::String(schema_type)) => schema_type.as_str(),
+            // 3.1では型を配列で指定できるため、最初の型を使用する
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|t| *t != "null")
+                .unwrap_or("null"),
+            _ if schema.get("properties").is_some() => "object",
+            _ => "",
+        };
+
+        Ok(match schema_type {
+            "object" => {
+                let mut object = Map::new();
+                let required = schema.get("required").and_then(Value::as_array);
+                if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                    for (name, property) in properties {
+                        let example = self.example_at(property, refs)?;
+                        let is_required = required
+                            .map(|required| required.iter().any(|r| r.as_str() == Some(name)))
+                            .unwrap_or(false);
+                        if !example.is_null() || is_required {
+                            object.insert(name.clone(), example);
+                        }
+                    }
+                }
+                Value::Object(object)
+            }
+            "array" => match schema.get("items") {
+                Some(items) => Value::Array(vec![self.example_at(items, refs)?]),
+                None => Value::Array(Vec::new()),
+            },
+            "string" => Value::String(
+                match schema.get("format").and_then(Value::as_str) {
+                    Some("date-time") => "2024-01-01T00:00:00Z",
+                    Some("date") => "2024-01-01",
+                    Some("email") => "user@example.com",
+                    Some("uuid") => "00000000-0000-0000-0000-000000000000",
+                    Some("uri" | "url") => "https://example.com",
+                    _ => "string",
+                }
+                .to_string(),
+            ),
+            "integer" => Value::from(schema.get("minimum").and_then(Value::as_i64).unwrap_or(0)),
+            "number" => Value::from(schema.get("minimum").and_then(Value::as_f64).unwrap_or(0.0)),
+            "boolean" => Value::Bool(false),
+            _ => Value::Null,
+        })
+    }
+
+    // パスアイテムまたはオペレーションのパラメータを$refを辿って返す関数
+    // 引数：item: &Value -> パスアイテムまたはオペレーション。不変参照
+    // 戻り値：AppResult<Vec<&Value>> -> パラメータの配列をAppResultでラップしたもの
+    fn parameters<'a>(&'a self, item: &'a Value) -> AppResult<Vec<&'a Value>> {
+        match item.get("parameters").and_then(Value::as_array) {
+            Some(parameters) => parameters.iter().map(|p| self.resolve(p)).collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+// リクエストボディまたはレスポンスからjsonのメディアタイプの定義を返す関数
+// application/jsonと、+jsonで終わるメディアタイプを対象とする
+// 引数：item: &Value -> contentを持つ定義。不変参照
+// 戻り値：Option<&Value> -> メディアタイプの定義。存在しない場合はNone
+pub fn json_media(item: &Value) -> Option<&Value> {
+    let content = item.get("content").and_then(Value::as_object)?;
+    content
+        .iter()
+        .find(|(media_type, _)| {
+            let media_type = media_type.split(';').next().unwrap_or_default().trim();
+            media_type == "application/json" || media_type.ends_with("+json")
+        })
+        .map(|(_, media)| media)
+}
+
+impl Operation<'_> {
+    // 指定した場所(path, query, header, cookie)のパラメータを返す関数
+    // 引数：location: &str -> パラメータの場所
+    // 戻り値：impl Iterator<Item = &Value> -> パラメータのイテレータ
+    pub fn parameters_in<'b>(&'b self, location: &'b str) -> impl Iterator<Item = &'b Value> + 'b {
+        self.parameters
+            .iter()
+            .copied()
+            .filter(move |p| p.get("in").and_then(Value::as_str) == Some(location))
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Context};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::types::{AppResult, InputConfigration, InputDataMap, InputSchema};
+
+// ステップとデータのexpect_schemaに指定されたスキーマファイルを読み込む関数
+// 読み込んだスキーマはコンパイルし、実行前に記述の誤りを検出する
+// 引数
+// - test_config: &mut InputConfigration -> テスト構成ファイルの構造体。可変参照
+// - input_data_map: &mut InputDataMap -> データの連想配列。可変参照
+// 戻り値：AppResult<()>
+pub fn load_schemas(
+    test_config: &mut InputConfigration,
+    input_data_map: &mut InputDataMap,
+) -> AppResult<()> {
+    let base_dir = test_config.base_dir.clone();
+
+    // データファイルのスキーマは、データファイルを宣言したファイルのディレクトリを基準に解決する
+    let step_schemas = test_config
+        .categories
+        .values_mut()
+        .flat_map(|category| category.steps.iter_mut())
+        .filter_map(|step| {
+            step.expect_schema
+                .as_mut()
+                .map(|schema| (schema, &base_dir))
+        });
+    let data_schemas = input_data_map
+        .values_mut()
+        .flat_map(|data| data.iter_mut())
+        .filter_map(|data| {
+            let schema_dir = data.base_dir.as_ref().unwrap_or(&base_dir);
+            data.expect_schema
+                .as_mut()
+                .map(|schema| (schema, schema_dir))
+        });
+
+    for (schema, base_dir) in step_schemas.chain(data_schemas) {
+        if let InputSchema::File(path) = schema {
+            *schema = InputSchema::Inline(load_schema_file(&base_dir.join(path.as_str()))?);
+        }
+        compile_schema(schema)?;
+    }
+
+    Ok(())
+}
+
+// スキーマファイルを読み込む関数
+// 拡張子が.yamlまたは.ymlの場合はyaml、それ以外はjsonとして読み込む
+// 引数：path: &Path -> スキーマファイルのパス。不変参照
+// 戻り値：AppResult<Value> -> スキーマのjsonの値をAppResultでラップしたもの
+fn load_schema_file(path: &Path) -> AppResult<Value> {
+    let load = || -> AppResult<Value> {
+        let reader = BufReader::new(File::open(path)?);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => Ok(serde_yaml::from_reader(reader)?),
+            _ => Ok(serde_json::from_reader(reader)?),
+        }
+    };
+    load().with_context(|| format!("Failed to load schema file: {}", path.display()))
+}
+
+// スキーマをコンパイルする関数
+// 引数：schema: &InputSchema -> 読み込み済みのスキーマ。不変参照
+// 戻り値：AppResult<JSONSchema> -> コンパイルしたスキーマをAppResultでラップしたもの
+pub fn compile_schema(schema: &InputSchema) -> AppResult<JSONSchema> {
+    match schema {
+        InputSchema::Inline(schema) => {
+            JSONSchema::compile(schema).map_err(|e| anyhow!("Invalid JSON Schema: {}", e))
+        }
+        InputSchema::File(path) => Err(anyhow!("Schema file not loaded: {}", path)),
+    }
+}
+
+// レスポンスボディをスキーマで検証する関数
+// 引数
+// - schema: &JSONSchema -> コンパイルしたスキーマ。不変参照
+// - body: &str -> レスポンスボディ。不変参照
+// 戻り値：Option<String> -> 検証に失敗した場合はその内容、成功した場合はNone
+pub fn validate_body(schema: &JSONSchema, body: &str) -> Option<String> {
+    let instance: Value = match serde_json::from_str(body) {
+        Ok(instance) => instance,
+        Err(e) => return Some(format!("response body is not json: {}", e)),
+    };
+
+    schema_errors(schema, &instance).map(|errors| format!("schema violations: [{}]", errors))
+}
+
+// 値をスキーマで検証し、エラーの内容を返す関数
+// エラーごとに値の中の位置とメッセージを「; 」区切りで連結する。位置が値そのものの場合は(root)とする
+// 引数
+// - schema: &JSONSchema -> コンパイルしたスキーマ。不変参照
+// - instance: &Value -> 検証する値。不変参照
+// 戻り値：Option<String> -> エラーの内容。適合する場合はNone
+pub fn schema_errors(schema: &JSONSchema, instance: &Value) -> Option<String> {
+    let errors: Vec<String> = match schema.validate(instance) {
+        Ok(()) => return None,
+        Err(errors) => errors
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                format!(
+                    "{}: {}",
+                    if path.is_empty() { "(root)" } else { &path },
+                    error
+                )
+            })
+            .collect(),
+    };
+    Some(errors.join("; "))
+}
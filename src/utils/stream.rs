@@ -0,0 +1,276 @@
+use anyhow::anyhow;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::Response;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+
+use super::json_includes;
+use super::types::{AppResult, ExpectEvent, ExpectEvents, InputData, InputStream, StreamFormat};
+
+// ストリームを読み込む時間の上限の既定値(ミリ秒)
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+// ストリーミングのレスポンスを読み込む範囲と、イベントの検証を格納する構造体
+#[derive(Debug, Clone)]
+pub struct StreamAssertion {
+    // 指定がない場合はContent-Typeから判定する
+    format: Option<StreamFormat>,
+    max_events: Option<usize>,
+    timeout: Duration,
+    type_field: String,
+    expect: ExpectEvents,
+}
+
+// 受信したイベントを格納する構造体
+#[derive(Debug)]
+struct StreamEvent {
+    event_type: Option<String>,
+    data: String,
+}
+
+// ステップとデータからストリーミングのレスポンスの検証を生成する関数
+// 引数
+// - stream: &InputStream -> ステップのストリームの指定。不変参照
+// - data: &InputData -> イベントの検証を含むデータ。不変参照
+// 戻り値：AppResult<StreamAssertion> -> 検証をAppResultでラップしたもの。max_eventsが0の場合はエラー
+pub fn gen_stream_assertion(stream: &InputStream, data: &InputData) -> AppResult<StreamAssertion> {
+    if stream.max_events == Some(0) {
+        return Err(anyhow!("max_events must be greater than 0"));
+    }
+
+    Ok(StreamAssertion {
+        format: stream.format,
+        max_events: stream.max_events,
+        timeout: Duration::from_millis(stream.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS)),
+        type_field: stream
+            .type_field
+            .clone()
+            .unwrap_or_else(|| "type".to_string()),
+        expect: data.expect_events.clone().unwrap_or_default(),
+    })
+}
+
+impl StreamAssertion {
+    // ストリーミングのレスポンスを範囲内で読み込み、イベントを検証する関数
+    // 読み込んだ内容をボディとしたレスポンスを作り直し、ボディの検証や詳細表示に使用する
+    // 引数：response: Response -> ストリーミングのレスポンス。所有権を移動する
+    // 戻り値：(Response, Vec<String>) -> 読み込んだ内容をボディとしたレスポンスと、検証に失敗した内容の配列
+    pub async fn read(&self, mut response: Response) -> (Response, Vec<String>) {
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let format = self.format.unwrap_or_else(|| detect_format(&headers));
+
+        let deadline = Instant::now() + self.timeout;
+        let mut parser = EventParser::new(format, &self.type_field);
+        let mut body: Vec<u8> = Vec::new();
+        let mut failures: Vec<String> = Vec::new();
+
+        // 上限の数のイベントを受信した時点で、接続を閉じる
+        while self
+            .max_events
+            .is_none_or(|max_events| parser.events.len() < max_events)
+        {
+            match timeout_at(deadline, response.chunk()).await {
+                Ok(Ok(Some(chunk))) => {
+                    body.extend_from_slice(&chunk);
+                    parser.feed(&chunk);
+                }
+                Ok(Ok(None)) => {
+                    parser.finish();
+                    break;
+                }
+                Ok(Err(e)) => {
+                    failures.push(format!("stream: {}", e));
+                    break;
+                }
+                // 時間の上限に達した場合は、それまでに受信したイベントを検証する
+                Err(_) => break,
+            }
+        }
+
+        let mut events = parser.events;
+        if let Some(max_events) = self.max_events {
+            events.truncate(max_events);
+        }
+        failures.extend(self.check(&events));
+
+        let mut rebuilt = http::Response::new(String::from_utf8_lossy(&body).to_string());
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        (Response::from(rebuilt), failures)
+    }
+
+    // 受信したイベントを検証する関数
+    // 予期したイベントは、受信したイベントの中からこの順に探す
+    // 引数：events: &[StreamEvent] -> 受信したイベントの配列
+    // 戻り値：Vec<String> -> 検証に失敗した内容の配列。全て成功した場合は空
+    fn check(&self, events: &[StreamEvent]) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        if let Some(count) = self.expect.count.filter(|count| *count != events.len()) {
+            failures.push(format!("events: {}, expect count: {}", events.len(), count));
+        }
+        if let Some(min_count) = self
+            .expect
+            .min_count
+            .filter(|min_count| *min_count > events.len())
+        {
+            failures.push(format!(
+                "events: {}, expect min count: {}",
+                events.len(),
+                min_count
+            ));
+        }
+
+        let mut cursor = 0;
+        for (index, expect) in self.expect.events.iter().enumerate() {
+            match events[cursor..]
+                .iter()
+                .position(|event| matches_event(event, expect))
+            {
+                Some(position) => cursor += position + 1,
+                None => failures.push(format!(
+                    "events[{}]: not received ({})",
+                    index,
+                    serde_json::to_string(expect).unwrap_or_default()
+                )),
+            }
+        }
+
+        failures
+    }
+}
+
+// Content-Typeからストリームの形式を判定する関数
+// 引数：headers: &HeaderMap -> レスポンスヘッダ。不変参照
+// 戻り値：StreamFormat -> text/event-streamの場合はsse、それ以外はndjson
+fn detect_format(headers: &HeaderMap) -> StreamFormat {
+    match headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(content_type) if content_type.contains("text/event-stream") => StreamFormat::Sse,
+        _ => StreamFormat::Ndjson,
+    }
+}
+
+// 受信したイベントが予期したイベントに一致するかどうかを判定する関数
+// 引数
+// - event: &StreamEvent -> 受信したイベント。不変参照
+// - expect: &ExpectEvent -> 予期したイベント。不変参照
+// 戻り値：bool -> 一致する場合はtrue
+fn matches_event(event: &StreamEvent, expect: &ExpectEvent) -> bool {
+    if expect.event_type.is_some() && expect.event_type != event.event_type {
+        return false;
+    }
+    if let Some(contains) = &expect.contains {
+        if !event.data.contains(contains.as_str()) {
+            return false;
+        }
+    }
+    match &expect.data {
+        None => true,
+        Some(Value::String(data)) => event.data == *data,
+        Some(data) => serde_json::from_str::<Value>(&event.data)
+            .map(|actual| json_includes(&actual, data))
+            .unwrap_or(false),
+    }
+}
+
+// 受信したバイト列を行に分割し、イベントを組み立てる構造体
+struct EventParser<'a> {
+    format: StreamFormat,
+    type_field: &'a str,
+    // 改行を受信していない行の途中のバイト列
+    buffer: Vec<u8>,
+    // sseで組み立て中のイベントの種類とデータの行
+    event_type: Option<String>,
+    data: Vec<String>,
+    events: Vec<StreamEvent>,
+}
+
+impl<'a> EventParser<'a> {
+    fn new(format: StreamFormat, type_field: &'a str) -> Self {
+        EventParser {
+            format,
+            type_field,
+            buffer: Vec::new(),
+            event_type: None,
+            data: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    // 受信したバイト列を追加し、改行までの行を処理する関数
+    // 引数：chunk: &[u8] -> 受信したバイト列
+    fn feed(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+        while let Some(position) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=position).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.line(line.trim_end_matches(['\n', '\r']));
+        }
+    }
+
+    // ストリームの終了時に、改行のない最後の行を処理する関数
+    // sseで空行を受信していないイベントは、仕様に従って破棄する
+    fn finish(&mut self) {
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&self.buffer).to_string();
+            self.buffer.clear();
+            self.line(line.trim_end_matches('\r'));
+        }
+    }
+
+    // 1行を処理する関数
+    // 引数：line: &str -> 改行を除いた行
+    fn line(&mut self, line: &str) {
+        match self.format {
+            StreamFormat::Ndjson => {
+                if line.trim().is_empty() {
+                    return;
+                }
+                let event_type = serde_json::from_str::<Value>(line).ok().and_then(|value| {
+                    value
+                        .get(self.type_field)
+                        .and_then(Value::as_str)
+                        .map(String::from)
+                });
+                self.events.push(StreamEvent {
+                    event_type,
+                    data: line.to_string(),
+                });
+            }
+            StreamFormat::Sse => {
+                // 空行でイベントを確定する。データのないイベントは破棄する
+                if line.is_empty() {
+                    let event_type = self.event_type.take();
+                    if !self.data.is_empty() {
+                        self.events.push(StreamEvent {
+                            event_type: event_type.or_else(|| Some("message".to_string())),
+                            data: self.data.join("\n"),
+                        });
+                        self.data.clear();
+                    }
+                    return;
+                }
+                // :から始まる行はコメント
+                if line.starts_with(':') {
+                    return;
+                }
+                let (field, value) = match line.split_once(':') {
+                    Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                    None => (line, ""),
+                };
+                match field {
+                    "event" => self.event_type = Some(value.to_string()),
+                    "data" => self.data.push(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
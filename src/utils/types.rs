@@ -1,58 +1,623 @@
 use anyhow::Error;
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+use jsonschema::JSONSchema;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::assertion::BodyAssertion;
+use super::body::RequestBody;
+use super::contract::Contract;
+use super::generator::Generator;
+use super::graphql::GraphqlAssertion;
+use super::stream::StreamAssertion;
+use super::websocket::WebSocketScenario;
 
 // テスト構成ファイルの構造体を定義する
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct InputConfigration {
+    // 環境やサービスで指定する場合は省略できる
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub base_url: String,
-    pub data: String,
+    // データファイルのパス。複数指定した場合はマージする
+    // ステップに直接データを記述する場合は省略できる
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub data: Vec<String>,
+    // サービス名とベースURLの連想配列
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub services: HashMap<String, String>,
+    // パスやヘッダの {key} の置換に使用する変数
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub variables: HashMap<String, String>,
+    // 全てのリクエストに付与するヘッダ
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub headers: HashMap<String, String>,
+    // --env で選択する環境の連想配列
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub environments: HashMap<String, InputEnvironment>,
+    // 全てのステップの所要時間の上限(ミリ秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<u64>,
+    // 所要時間が上限を超えた場合の扱い
+    #[serde(default, skip_serializing_if = "MaxDurationMode::is_default")]
+    pub max_duration_mode: MaxDurationMode,
+    // リクエストとレスポンスを検証するOpenAPIドキュメントのパス
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openapi: Option<String>,
+    #[serde(default)]
     pub init: Vec<InputStep>,
+    #[serde(serialize_with = "sorted")]
     pub categories: HashMap<String, InputCaterogy>,
+    // テスト構成ファイルのディレクトリの絶対パス
+    // 相対パスの解決に使用する
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+    // openapiに指定されたドキュメントから読み込んだ契約
+    #[serde(skip)]
+    pub contract: Option<Contract>,
+    // データとヘッダの {{ expr }} を評価する生成器
+    // フラットされたステップごとに評価するため、initステップとテストステップで共有する
+    #[serde(skip)]
+    pub generator: Mutex<Generator>,
+}
+
+// 環境の構造体を定義する
+// 指定された値はルートの値を上書きする
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InputEnvironment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub services: HashMap<String, String>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub variables: HashMap<String, String>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub headers: HashMap<String, String>,
 }
 
 // カテゴリーの構造体を定義する
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct InputCaterogy {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub login: Option<String>,
+    // カテゴリ内のステップのリクエスト先のサービス
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    // カテゴリ内の全てのステップに付与するヘッダ
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub headers: HashMap<String, String>,
+    // カテゴリ内の全てのステップに適用するマトリクス
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub matrix: InputMatrix,
+    // カテゴリ内の全てのステップの所要時間の上限(ミリ秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<u64>,
     pub steps: Vec<InputStep>,
 }
 
 // ステップの構造体を定義する
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct InputStep {
     pub name: String,
     pub path: String,
     pub method: String,
+    // リクエスト先のサービス。カテゴリの指定より優先される
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    // ステップに直接データを記述する場合は省略できる
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub ref_data: String,
+    // ステップに直接記述するデータ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<InputData>>,
+    // ステップに付与するヘッダ
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted"
+    )]
+    pub headers: HashMap<String, String>,
+    // ステップに適用するマトリクス。カテゴリのマトリクスと同じ名前のパラメータは上書きする
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub matrix: InputMatrix,
+    // ステップの所要時間の上限(ミリ秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<u64>,
+    // レスポンスボディを検証するJSON Schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect_schema: Option<InputSchema>,
+    // リクエストボディの形式。指定がない場合はjson
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_type: Option<BodyType>,
+    // json以外も含むレスポンスボディの検証
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect_body: Option<ExpectBody>,
+    // GraphQLのリクエスト。指定した場合は、クエリと変数をjsonのボディとして送信する
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<InputGraphql>,
+    // WebSocketのステップ。指定した場合は、接続してデータのメッセージを順に送受信する
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub websocket: Option<InputWebSocket>,
+    // ストリーミングのレスポンスを読み込む範囲。指定した場合は、イベントを検証する
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<InputStream>,
     pub option: InputOption,
 }
 #[derive(Debug)]
 pub struct FlattenStep {
     pub name: String,
+    // サービスを解決したベースURL
+    pub base_url: String,
     pub path: String,
     pub method: String,
+    // 優先順位に従ってマージ・置換済みのヘッダ
+    pub headers: HeaderMap,
+    // パスの置換に使用する変数
+    pub variables: HashMap<String, Value>,
+    // データ > ステップ > カテゴリ > ルートの優先順位で解決した所要時間の上限
+    pub max_duration: Option<Duration>,
+    // データ > ステップの優先順位で解決し、コンパイルしたJSON Schema
+    pub expect_schema: Option<Arc<JSONSchema>>,
+    // データ > ステップの優先順位で解決し、正規表現とXPathを検証済みのレスポンスボディの検証
+    pub expect_body: Option<BodyAssertion>,
+    // GraphQLのステップの場合は、レスポンスのerrorsとdataの検証
+    pub graphql: Option<GraphqlAssertion>,
+    // WebSocketのステップの場合は、送受信するメッセージのシナリオ
+    pub websocket: Option<WebSocketScenario>,
+    // ストリーミングのステップの場合は、読み込む範囲とイベントの検証
+    pub stream: Option<StreamAssertion>,
+    // ボディの形式に従って生成し、ファイルを読み込み済みのリクエストボディ
+    pub request_body: Option<RequestBody>,
     pub input_data: InputData,
 }
 
+// テスト実行のオプションを格納する構造体
+pub struct RunOptions {
+    // リクエストの詳細を表示するかどうか
+    pub print_flag: bool,
+    // クッキーが取得できなかった場合に、カテゴリを飛ばしてテストを続行するかどうか
+    pub cookie_error_continue: bool,
+    // 失敗したステップを再現するcurlコマンドを表示するかどうか
+    pub curl_on_failure: bool,
+    // curlコマンドの秘匿情報を伏せるかどうか
+    pub redact: bool,
+}
+
 // オプションの構造体を定義する
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct InputOption {
     pub body: bool,
     pub query: bool,
 }
 
+// マトリクスのパラメータ名と値の配列の連想配列を定義する
+// ステップ名にパラメータを記述された順に含めるため、順序を保持する
+pub type InputMatrix = IndexMap<String, Vec<Value>>;
+
 // Jsonで与えられたデータの内容を格納する連想配列を定義する
 // 引数：String -> jsonのキー。所有権を移動する
 pub type InputDataMap = HashMap<String, Vec<InputData>>;
 
 // Jsonの内部データを格納する連想配列を定義する
-#[derive(Debug, Deserialize, Clone)]
+// インポートなどで生成する場合は、指定しないフィールドをDefaultで補う
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct InputData {
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted_option"
+    )]
     pub body: Option<HashMap<String, Value>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted_option"
+    )]
     pub query: Option<HashMap<String, Value>>,
-    pub expect_status: u16,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted_option"
+    )]
+    pub headers: Option<HashMap<String, String>>,
+    pub expect_status: ExpectStatus,
+    // データの所要時間の上限(ミリ秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<u64>,
+    // レスポンスボディを検証するJSON Schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect_schema: Option<InputSchema>,
+    // モックサーバーがこのデータのリクエストに返すレスポンスボディ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mock_response: Option<Value>,
+    // リクエストボディの形式。ステップの指定より優先される
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_type: Option<BodyType>,
+    // body_typeがrawの場合に送信する文字列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_body: Option<String>,
+    // body_typeがrawまたはbinaryの場合に送信するファイルのパス。データを宣言したテスト構成ファイルのディレクトリを基準に解決する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_file: Option<String>,
+    // リクエストのContent-Type。データのContent-Typeヘッダとして扱う
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    // json以外も含むレスポンスボディの検証。ステップの指定より優先される
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_body: Option<ExpectBody>,
+    // GraphQLのステップで送信する変数
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted_option"
+    )]
+    pub variables: Option<HashMap<String, Value>>,
+    // GraphQLのレスポンスにerrorsが含まれることを予期するかどうか。指定がない場合はfalse
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_errors: Option<bool>,
+    // GraphQLのレスポンスのdataを起点とするJSONPathと、予期する値の連想配列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_data: Option<IndexMap<String, Value>>,
+    // WebSocketのステップで順に送受信するメッセージ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<InputMessage>>,
+    // ストリーミングのステップで受信するイベントの検証
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_events: Option<ExpectEvents>,
+    // データファイルを宣言したテスト構成ファイルのディレクトリ
+    // データファイルに書かれた相対パスはこのディレクトリを基準に解決する
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
+}
+
+// GraphQLのリクエストを定義する
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct InputGraphql {
+    // クエリの文字列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    // クエリを記述したファイルのパス。テスト構成ファイルのディレクトリを基準に解決する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_file: Option<String>,
+    // 実行するオペレーションの名前
+    #[serde(
+        default,
+        alias = "operationName",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub operation_name: Option<String>,
+}
+
+// WebSocketのステップを定義する
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct InputWebSocket {
+    // 接続とメッセージの受信を待つ時間の上限(ミリ秒)。指定がない場合は5000
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+// WebSocketで送受信するメッセージを定義する
+// sendを指定した場合は送信し、expectかcontainsを指定した場合は次のメッセージを受信して検証する
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct InputMessage {
+    // 送信するメッセージ。文字列以外はjsonの文字列として送信する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send: Option<Value>,
+    // 受信するメッセージ。文字列は一致を、それ以外はjsonとして含まれることを検証する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<Value>,
+    // 受信するメッセージに含まれるべき文字列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+    // 受信を待つ時間の上限(ミリ秒)。ステップの指定より優先される
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+// ストリーミングのレスポンスを読み込む範囲を定義する
+// max_eventsのイベントを受信するか、timeout_msが経過するか、ストリームが終了するまで読み込む
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct InputStream {
+    // ストリームの形式。指定がない場合はContent-Typeから判定する
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<StreamFormat>,
+    // 読み込むイベントの数の上限
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_events: Option<usize>,
+    // 読み込む時間の上限(ミリ秒)。指定がない場合は5000
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    // ndjsonの場合に、イベントの種類として扱うフィールド名。指定がない場合はtype
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_field: Option<String>,
+}
+
+// ストリームの形式を定義する
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    // text/event-stream
+    Sse,
+    // 1行に1つのjsonを返す形式
+    Ndjson,
+}
+
+// ストリーミングのレスポンスで受信するイベントの検証を定義する
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ExpectEvents {
+    // 受信するイベントの数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    // 受信するイベントの数の下限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_count: Option<usize>,
+    // 受信するべきイベント。間に他のイベントを挟んでもよいが、この順に受信する必要がある
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub events: Vec<ExpectEvent>,
+}
+
+// 受信するべきイベントを定義する
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ExpectEvent {
+    // イベントの種類。sseはevent、ndjsonはtype_fieldの値
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+    // イベントのデータ。文字列は一致を、それ以外はjsonとして含まれることを検証する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    // イベントのデータに含まれるべき文字列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+}
+
+// レスポンスボディの検証を定義する
+// テキストとして検証し、xpathを指定した場合はXMLとして検証する
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ExpectBody {
+    // ボディに含まれるべき文字列
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub contains: Vec<String>,
+    // ボディに含まれてはならない文字列
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub not_contains: Vec<String>,
+    // ボディにマッチするべき正規表現
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub regex: Vec<String>,
+    // XMLのボディに対するXPathの検証
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub xpath: Vec<ExpectXPath>,
+}
+
+// XPathの検証を定義する
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ExpectXPath {
+    pub path: String,
+    // XPathの評価結果の文字列と比較する値。指定がない場合はノードが存在することを検証する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+}
+
+// リクエストボディの形式を定義する
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyType {
+    // bodyをjsonとして送信する
+    #[default]
+    Json,
+    // bodyをapplication/x-www-form-urlencodedとして送信する
+    Form,
+    // bodyをmultipart/form-dataとして送信する。値にfileを指定した場合はファイルを添付する
+    Multipart,
+    // raw_bodyの文字列、またはbody_fileのファイルの内容をテキストとしてそのまま送信する
+    Raw,
+    // body_fileのファイルの内容をそのまま送信する
+    Binary,
+}
+
+// JSON Schemaの指定を定義する
+// スキーマファイルのパス、またはスキーマを直接記述する
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum InputSchema {
+    File(String),
+    Inline(Value),
+}
+
+// 所要時間が上限を超えた場合の扱いを定義する
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MaxDurationMode {
+    // テストを失敗とする
+    #[default]
+    Fail,
+    // テストは成功とし、メッセージに警告を含める
+    Warn,
+}
+
+// 予期するステータスコードの構造体を定義する
+// 数値、"2xx"のようなクラス、またはそれらの配列で指定する
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(try_from = "Value", into = "Value")]
+pub struct ExpectStatus(pub Vec<StatusPattern>);
+
+// 既定値は200とする
+impl Default for ExpectStatus {
+    fn default() -> Self {
+        ExpectStatus(vec![StatusPattern::Code(200)])
+    }
+}
+
+// ステータスコードのパターンを定義する
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusPattern {
+    // 完全に一致するステータスコード
+    Code(u16),
+    // 百の位が一致するステータスコード(例: 4 -> 4xx)
+    Class(u16),
+}
+
+impl MaxDurationMode {
+    // 既定値かどうかを返す関数
+    // 戻り値：bool
+    pub fn is_default(&self) -> bool {
+        *self == MaxDurationMode::default()
+    }
+}
+
+impl ExpectStatus {
+    // ステータスコードに一致するパターンを返す関数
+    // 引数：status: u16 -> ステータスコード
+    // 戻り値：Option<&StatusPattern> -> 一致したパターン。一致しない場合はNone
+    pub fn matches(&self, status: u16) -> Option<&StatusPattern> {
+        self.0.iter().find(|pattern| match pattern {
+            StatusPattern::Code(code) => *code == status,
+            StatusPattern::Class(class) => status / 100 == *class,
+        })
+    }
+
+    // 単一のステータスコードの指定かどうかを返す関数
+    // 戻り値：bool
+    pub fn is_single_code(&self) -> bool {
+        matches!(self.0.as_slice(), [StatusPattern::Code(_)])
+    }
+}
+
+impl fmt::Display for ExpectStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let patterns: Vec<String> = self.0.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}", patterns.join(" or "))
+    }
+}
+
+impl fmt::Display for StatusPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusPattern::Code(code) => write!(f, "{}", code),
+            StatusPattern::Class(class) => write!(f, "{}xx", class),
+        }
+    }
+}
+
+impl TryFrom<Value> for ExpectStatus {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let patterns = match value {
+            Value::Array(values) => values
+                .into_iter()
+                .map(StatusPattern::try_from)
+                .collect::<Result<Vec<StatusPattern>, String>>()?,
+            value => vec![StatusPattern::try_from(value)?],
+        };
+        if patterns.is_empty() {
+            return Err("expect_status is empty".to_string());
+        }
+        Ok(ExpectStatus(patterns))
+    }
+}
+
+impl From<ExpectStatus> for Value {
+    fn from(expect_status: ExpectStatus) -> Self {
+        let mut values: Vec<Value> = expect_status
+            .0
+            .iter()
+            .map(|pattern| match pattern {
+                StatusPattern::Code(code) => Value::from(*code),
+                StatusPattern::Class(_) => Value::String(pattern.to_string()),
+            })
+            .collect();
+        match values.len() {
+            1 => values.remove(0),
+            _ => Value::Array(values),
+        }
+    }
+}
+
+impl TryFrom<Value> for StatusPattern {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid expect_status: {}", value);
+        match &value {
+            Value::Number(n) => n
+                .as_u64()
+                .and_then(|n| u16::try_from(n).ok())
+                .map(StatusPattern::Code)
+                .ok_or_else(invalid),
+            Value::String(s) => {
+                let s = s.trim().to_lowercase();
+                match s.strip_suffix("xx") {
+                    Some(class) if class.len() == 1 => class
+                        .parse::<u16>()
+                        .ok()
+                        .filter(|class| (1..=5).contains(class))
+                        .map(StatusPattern::Class)
+                        .ok_or_else(invalid),
+                    Some(_) => Err(invalid()),
+                    None => s.parse().map(StatusPattern::Code).map_err(|_| invalid()),
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
 }
 
 // テストの結果を格納する構造体を定義する
@@ -63,12 +628,86 @@ pub struct OutputResult {
     pub status: String,
     pub duration: f64,
     pub message: String,
+    // OpenAPIドキュメントへの契約違反。テストの成否には影響しない
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub contract_violations: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct OutputData {
     pub base_url: String,
     pub results: Vec<OutputResult>,
+    // OpenAPIドキュメントが指定された場合のカバレッジ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<OutputCoverage>,
+}
+
+// OpenAPIドキュメントのカバレッジを格納する構造体を定義する
+#[derive(Debug, Serialize)]
+pub struct OutputCoverage {
+    // オペレーションの数と、実行したオペレーションの数
+    pub operations: usize,
+    pub tested_operations: usize,
+    pub operation_percent: f64,
+    // 記述されたレスポンスの数と、観測したレスポンスの数
+    pub responses: usize,
+    pub tested_responses: usize,
+    pub response_percent: f64,
+    // 実行していないオペレーション(例: GET /api/users/{id})
+    pub untested_operations: Vec<String>,
+    // 観測していないレスポンス(例: GET /api/users/{id} 404)
+    pub untested_responses: Vec<String>,
+}
+
+// 単一の値と配列の両方を受け付けて配列としてデシリアライズする関数
+// 引数：deserializer: D -> デシリアライザ
+// 戻り値：Result<Vec<T>, D::Error> -> 値の配列
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+// 連想配列をキーの順に並べてシリアライズする関数
+// 出力するファイルの内容を実行ごとに変えないために使用する
+// 引数
+// - map: &HashMap<String, V> -> シリアライズする連想配列
+// - serializer: S -> シリアライザ
+// 戻り値：Result<S::Ok, S::Error>
+fn sorted<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+// Optionの連想配列をキーの順に並べてシリアライズする関数
+// 引数
+// - map: &Option<HashMap<String, V>> -> シリアライズする連想配列
+// - serializer: S -> シリアライザ
+// 戻り値：Result<S::Ok, S::Error>
+fn sorted_option<S, V>(map: &Option<HashMap<String, V>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    match map {
+        Some(map) => sorted(map, serializer),
+        None => serializer.serialize_none(),
+    }
 }
 
 // anyhowを使用したResult型のエイリアス
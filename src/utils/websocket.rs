@@ -0,0 +1,198 @@
+use anyhow::anyhow;
+use futures::{SinkExt, StreamExt};
+use reqwest::header::HeaderMap;
+use reqwest::Response;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time::timeout;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+use super::json_includes;
+use super::types::{AppResult, InputData, InputMessage, InputWebSocket};
+
+// 接続とメッセージの受信を待つ時間の上限の既定値(ミリ秒)
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+// WebSocketで送受信するメッセージのシナリオを格納する構造体
+#[derive(Debug, Clone)]
+pub struct WebSocketScenario {
+    // 接続とメッセージの受信を待つ時間の上限
+    timeout: Duration,
+    messages: Vec<InputMessage>,
+}
+
+// ステップとデータからWebSocketのシナリオを生成する関数
+// 引数
+// - websocket: &InputWebSocket -> ステップのWebSocketの指定。不変参照
+// - data: &InputData -> 送受信するメッセージを含むデータ。不変参照
+// 戻り値：AppResult<WebSocketScenario> -> シナリオをAppResultでラップしたもの。送受信しないメッセージがある場合はエラー
+pub fn gen_websocket_scenario(
+    websocket: &InputWebSocket,
+    data: &InputData,
+) -> AppResult<WebSocketScenario> {
+    let messages = data.messages.clone().unwrap_or_default();
+    if let Some(index) = messages.iter().position(|message| {
+        message.send.is_none() && message.expect.is_none() && message.contains.is_none()
+    }) {
+        return Err(anyhow!(
+            "send, expect or contains is required for messages[{}]",
+            index
+        ));
+    }
+
+    Ok(WebSocketScenario {
+        timeout: Duration::from_millis(websocket.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS)),
+        messages,
+    })
+}
+
+// WebSocketに接続し、シナリオのメッセージを順に送受信する関数
+// 受信したメッセージは1行ずつレスポンスボディとして返す
+// ハンドシェイクで101以外のステータスが返された場合は、そのレスポンスを返してシナリオは実行しない
+// 引数
+// - url: &str -> 接続先のURL。http(s)はws(s)に置き換える
+// - headers: HeaderMap -> ハンドシェイクのリクエストヘッダ。所有権を移動する
+// - scenario: &WebSocketScenario -> 送受信するメッセージのシナリオ。不変参照
+// 戻り値：AppResult<(Response, Vec<String>)> -> ハンドシェイクのレスポンスと、検証に失敗した内容の配列をAppResultでラップしたもの
+pub async fn run_websocket(
+    url: &str,
+    headers: HeaderMap,
+    scenario: &WebSocketScenario,
+) -> AppResult<(Response, Vec<String>)> {
+    let url = match url.split_once("://") {
+        Some(("http", rest)) => format!("ws://{}", rest),
+        Some(("https", rest)) => format!("wss://{}", rest),
+        _ => url.to_string(),
+    };
+    let mut request = url.as_str().into_client_request()?;
+    request.headers_mut().extend(headers);
+
+    let (mut stream, handshake) = match timeout(scenario.timeout, connect_async(request)).await {
+        Ok(Ok(connected)) => connected,
+        // 101以外のステータスは、ステータスコードの検証で扱う
+        Ok(Err(WsError::Http(response))) => {
+            let (parts, body) = response.into_parts();
+            let response = http::Response::from_parts(parts, body.unwrap_or_default());
+            return Ok((Response::from(response), Vec::new()));
+        }
+        Ok(Err(e)) => return Err(anyhow!("Failed to connect websocket: {} ({})", url, e)),
+        Err(_) => {
+            return Err(anyhow!(
+                "Timed out connecting websocket: {} ({}ms)",
+                url,
+                scenario.timeout.as_millis()
+            ))
+        }
+    };
+
+    let mut received: Vec<String> = Vec::new();
+    let mut failures: Vec<String> = Vec::new();
+
+    for (index, message) in scenario.messages.iter().enumerate() {
+        if let Some(send) = &message.send {
+            let text = match send {
+                Value::String(text) => text.clone(),
+                value => value.to_string(),
+            };
+            if let Err(e) = stream.send(Message::Text(text)).await {
+                failures.push(format!("messages[{}]: failed to send: {}", index, e));
+                break;
+            }
+        }
+
+        if message.expect.is_none() && message.contains.is_none() {
+            continue;
+        }
+
+        // ping, pongなどの制御フレームは読み飛ばし、次のテキストかバイナリのメッセージを待つ
+        let wait = message
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(scenario.timeout);
+        let next = timeout(wait, async {
+            loop {
+                match stream.next().await {
+                    Some(Ok(Message::Text(text))) => return Ok(Some(text)),
+                    Some(Ok(Message::Binary(bytes))) => {
+                        return Ok(Some(String::from_utf8_lossy(&bytes).to_string()))
+                    }
+                    Some(Ok(Message::Close(_))) | None => return Ok(None),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e),
+                }
+            }
+        })
+        .await;
+
+        let text = match next {
+            Ok(Ok(Some(text))) => text,
+            Ok(Ok(None)) => {
+                failures.push(format!("messages[{}]: connection closed", index));
+                break;
+            }
+            Ok(Err(e)) => {
+                failures.push(format!("messages[{}]: {}", index, e));
+                break;
+            }
+            Err(_) => {
+                failures.push(format!(
+                    "messages[{}]: timed out after {}ms",
+                    index,
+                    wait.as_millis()
+                ));
+                break;
+            }
+        };
+
+        if let Some(detail) = check_message(message, &text) {
+            failures.push(format!("messages[{}]: {}", index, detail));
+        }
+        received.push(text);
+
+        // 予期しないメッセージを受信した場合は、以降のシナリオを実行しない
+        if !failures.is_empty() {
+            break;
+        }
+    }
+
+    // 切断に失敗しても、シナリオの結果には影響しない
+    let _ = stream.close(None).await;
+
+    let (parts, _) = handshake.into_parts();
+    let response = http::Response::from_parts(parts, received.join("\n"));
+    Ok((Response::from(response), failures))
+}
+
+// 受信したメッセージを検証する関数
+// 引数
+// - message: &InputMessage -> シナリオのメッセージ。不変参照
+// - text: &str -> 受信したメッセージ
+// 戻り値：Option<String> -> 検証に失敗した内容。成功した場合はNone
+fn check_message(message: &InputMessage, text: &str) -> Option<String> {
+    if let Some(contains) = message
+        .contains
+        .as_ref()
+        .filter(|contains| !text.contains(*contains))
+    {
+        return Some(format!("{} does not contain: {}", text, contains));
+    }
+
+    let matched = match &message.expect {
+        None => true,
+        Some(Value::String(expect)) => text == expect,
+        Some(expect) => serde_json::from_str::<Value>(text)
+            .map(|actual| json_includes(&actual, expect))
+            .unwrap_or(false),
+    };
+    if matched {
+        None
+    } else {
+        Some(format!(
+            "{}, expect: {}",
+            text,
+            message.expect.as_ref().unwrap()
+        ))
+    }
+}